@@ -1,54 +1,143 @@
 use std::fmt;
 
+/// An action chosen by a network, as an index into the game's
+/// `ActionSpace`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Action(pub usize);
+
+impl Action {
+    /// One hot encoding over `n` actions.
+    pub fn vec(&self, n: usize) -> Vec<f32> {
+        (0..n).map(|i| if i == self.0 { 1. } else { 0. }).collect()
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<usize> for Action {
+    fn from(i: usize) -> Action {
+        Action(i)
+    }
+}
+
+impl<'a> From<&'a Action> for usize {
+    fn from(a: &'a Action) -> usize {
+        a.0
+    }
+}
+
+/// The actions a game accepts, by name. Networks get one output per action.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActionSpace {
+    pub names: Vec<String>,
+}
+
+impl ActionSpace {
+    pub fn new(names: &[&str]) -> Self {
+        ActionSpace {
+            names: names.iter().map(|n| n.to_string()).collect(),
+        }
+    }
+    /// Up, right, down and left, as used by the grid games.
+    pub fn directions() -> Self {
+        let names: Vec<_> = Direction::all().iter().map(|d| d.to_string()).collect();
+        ActionSpace { names: names }
+    }
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+    pub fn all(&self) -> Vec<Action> {
+        (0..self.len()).map(Action).collect()
+    }
+    pub fn name(&self, a: &Action) -> &str {
+        match self.names.get(a.0) {
+            Some(name) => name,
+            None => panic!(format!("Bad action value: {}", a.0)),
+        }
+    }
+}
+
+/// A move on the board of the grid games.
 #[derive(Debug, PartialEq, Clone, Copy)]
-pub enum Action {
+pub enum Direction {
     Up,
     Right,
     Down,
     Left,
 }
 
-impl Action {
-    pub fn all() -> Vec<Action> {
-        use Action::*;
+impl Direction {
+    pub fn all() -> Vec<Direction> {
+        use self::Direction::*;
         vec![Up, Right, Down, Left]
     }
-    pub fn vec(&self) -> Vec<f32> {
-        let i: usize = self.into();
-        (0..4).map(|n| if n == i { 1. } else { 0. }).collect()
+    pub fn opposite(&self) -> Direction {
+        match *self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+        }
+    }
+    /// The two directions at right angles to this one.
+    pub fn perpendicular(&self) -> [Direction; 2] {
+        match *self {
+            Direction::Up | Direction::Down => [Direction::Left, Direction::Right],
+            Direction::Right | Direction::Left => [Direction::Up, Direction::Down],
+        }
     }
 }
 
-impl fmt::Display for Action {
+impl fmt::Display for Direction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Action::Up => write!(f, "Up"),
-            Action::Right => write!(f, "Right"),
-            Action::Down => write!(f, "Down"),
-            Action::Left => write!(f, "Left"),
+            Direction::Up => write!(f, "Up"),
+            Direction::Right => write!(f, "Right"),
+            Direction::Down => write!(f, "Down"),
+            Direction::Left => write!(f, "Left"),
         }
     }
 }
 
-impl From<usize> for Action {
-    fn from(i: usize) -> Action {
-        match i {
-            0 => Action::Up,
-            1 => Action::Right,
-            2 => Action::Down,
-            3 => Action::Left,
+impl<'a> From<&'a Action> for Direction {
+    fn from(a: &'a Action) -> Direction {
+        match a.0 {
+            0 => Direction::Up,
+            1 => Direction::Right,
+            2 => Direction::Down,
+            3 => Direction::Left,
             i => panic!(format!("Bad action value: {}", i)),
         }
     }
 }
 
-impl<'a> From<&'a Action> for usize {
-    fn from(a: &'a Action) -> usize {
-        match *a {
-            Action::Up => 0,
-            Action::Right => 1,
-            Action::Down => 2,
-            Action::Left => 3,
+impl From<Direction> for Action {
+    fn from(d: Direction) -> Action {
+        match d {
+            Direction::Up => Action(0),
+            Direction::Right => Action(1),
+            Direction::Down => Action(2),
+            Direction::Left => Action(3),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_directions() {
+        let space = ActionSpace::directions();
+        assert_eq!(space.len(), 4);
+        for d in Direction::all() {
+            let action: Action = d.into();
+            assert_eq!(Direction::from(&action), d);
+            assert_eq!(space.name(&action), d.to_string());
         }
     }
 }
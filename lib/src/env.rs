@@ -0,0 +1,258 @@
+use action::{Action, ActionSpace};
+use game::{Game, GameState, IntoGameParameters, RenderingInfo};
+use rng::RcRng;
+use HeadlessSys;
+
+use ndarray::prelude::*;
+
+/// Extra details about a step that agents don't normally learn from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Info {
+    /// Steps taken since the last reset.
+    pub steps: usize,
+    /// Sum of the rewards since the last reset.
+    pub score: f32,
+    /// Whether the episode ended by running out of steps.
+    pub truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<usize>,
+}
+
+/// Drives a game directly, one action at a time, for agents that live
+/// outside of spatium. Seeds are for `XorShiftRng` and must not be all
+/// zeros.
+pub struct Env {
+    game: Box<Game + Send>,
+    inputs: usize,
+    actions: ActionSpace,
+    steps: usize,
+    score: f32,
+}
+
+impl Env {
+    pub fn new<G: IntoGameParameters>(params: G, seed: [u32; 4]) -> Result<Env, String> {
+        let params = params.into_parameters()?;
+        Ok(Env::from_game(params.into_game(RcRng::from_seed(seed))))
+    }
+    /// Wrap a game that isn't one of spatium's own.
+    pub fn from_game(game: Box<Game + Send>) -> Env {
+        let (inputs, actions) = game.io();
+        Env {
+            game: game,
+            inputs: inputs,
+            actions: actions,
+            steps: 0,
+            score: 0.,
+        }
+    }
+    /// Start a new episode and return the first observation.
+    pub fn reset(&mut self, seed: [u32; 4]) -> GameState {
+        let (state, reward, _) = self.game.reset(RcRng::from_seed(seed));
+        self.steps = 0;
+        self.score = reward;
+        state
+    }
+    /// Take an action, returning the observation, the reward for this step,
+    /// whether the episode is over and some info. Panics if the episode is
+    /// already over.
+    pub fn step(&mut self, action: &Action) -> (GameState, f32, bool, Info) {
+        if action.0 >= self.actions.len() {
+            panic!(format!("Bad action value: {}", action.0));
+        }
+
+        let (state, reward, done) = self.game.step(&HeadlessSys, action);
+        self.steps += 1;
+        self.score += reward;
+
+        let info = Info {
+            steps: self.steps,
+            score: self.score,
+            truncated: self.game.truncated(),
+            level: self.game.level(),
+        };
+        (state, reward, done, info)
+    }
+    /// Length of an observation flattened into a row.
+    pub fn observation_space(&self) -> usize {
+        self.inputs
+    }
+    pub fn action_space(&self) -> &ActionSpace {
+        &self.actions
+    }
+    pub fn render(&self) -> RenderingInfo {
+        self.game.rendering_info()
+    }
+}
+
+/// Runs copies of a game in lockstep so one batched forward pass can pick
+/// the actions for all of them. A copy that finishes its episode starts the
+/// next one straight away.
+pub struct VecEnv {
+    envs: Vec<Env>,
+    states: Vec<GameState>,
+    rng: RcRng,
+}
+
+impl VecEnv {
+    pub fn new<G: IntoGameParameters>(params: G, n: usize, seed: [u32; 4]) -> Result<VecEnv, String> {
+        let params = params.into_parameters()?;
+        if n == 0 {
+            return Err("A VecEnv needs at least one copy of the game".into());
+        }
+        let mut rng = RcRng::from_seed(seed);
+
+        let mut envs = vec![];
+        for _ in 0..n {
+            let seed = rng.seed();
+            envs.push(Env::new(params.clone(), seed)?);
+        }
+
+        Ok(VecEnv {
+            envs: envs,
+            states: vec![],
+            rng: rng,
+        })
+    }
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+    /// Start a new episode in every copy, returning one observation per row.
+    pub fn reset(&mut self) -> Array2<f32> {
+        let mut states = vec![];
+        for env in self.envs.iter_mut() {
+            states.push(env.reset(self.rng.seed()));
+        }
+        self.states = states;
+        self.observations()
+    }
+    /// Take one action in each copy. When a copy finishes, its row of the
+    /// observations is the start of its next episode, while its reward, done
+    /// flag and info are for the step that ended the last one. Panics if
+    /// `reset` hasn't been called.
+    pub fn step(&mut self, actions: &[Action]) -> (Array2<f32>, Vec<f32>, Vec<bool>, Vec<Info>) {
+        assert!(!self.states.is_empty(), "Call reset before stepping a VecEnv");
+        assert_eq!(actions.len(), self.envs.len());
+
+        let mut rewards = vec![];
+        let mut dones = vec![];
+        let mut infos = vec![];
+        for (i, env) in self.envs.iter_mut().enumerate() {
+            let (state, reward, done, info) = env.step(&actions[i]);
+            self.states[i] = if done {
+                env.reset(self.rng.seed())
+            } else {
+                state
+            };
+            rewards.push(reward);
+            dones.push(done);
+            infos.push(info);
+        }
+
+        (self.observations(), rewards, dones, infos)
+    }
+    pub fn observation_space(&self) -> usize {
+        self.envs[0].observation_space()
+    }
+    pub fn action_space(&self) -> &ActionSpace {
+        self.envs[0].action_space()
+    }
+    fn observations(&self) -> Array2<f32> {
+        let mut observations: Array2<f32> =
+            Array::zeros((self.states.len(), self.observation_space()));
+        for (i, state) in self.states.iter().enumerate() {
+            let row: Array2<f32> = state.into();
+            observations.row_mut(i).assign(&row.row(0));
+        }
+        observations
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use action::Direction;
+    use game::{Game1Parameters, GameParameters, MazeParameters};
+    use ndarray::Array2;
+
+    fn env() -> Env {
+        let maze = GameParameters::Maze(MazeParameters {
+            max_steps: 20,
+            ..Default::default()
+        });
+        Env::new(maze, [1, 2, 3, 4]).unwrap()
+    }
+
+    #[test]
+    fn test_episode() {
+        let mut env = env();
+        assert_eq!(env.action_space().len(), 4);
+
+        let state = env.reset([5, 6, 7, 8]);
+        let x: Array2<f32> = (&state).into();
+        assert_eq!(x.shape(), &[1, env.observation_space()]);
+
+        let mut steps = 0;
+        loop {
+            let (_, _, done, info) = env.step(&Action(steps % 4));
+            steps += 1;
+            assert_eq!(info.steps, steps);
+            if done {
+                break;
+            }
+        }
+        assert!(steps <= 20);
+    }
+
+    #[test]
+    fn test_seeded_reset() {
+        let mut env = Env::new(GameParameters::Game1(Default::default()), [1, 2, 3, 4]).unwrap();
+
+        // the same seed starts the same board
+        let first = env.reset([5, 6, 7, 8]);
+        env.step(&Action(0));
+        let again = env.reset([5, 6, 7, 8]);
+        assert_eq!(first.arr, again.arr);
+    }
+
+    #[test]
+    fn test_vec_env() {
+        let game = GameParameters::Game1(Game1Parameters {
+            max_steps: 5,
+            random: false,
+            step_penalty: 0.1,
+            ..Default::default()
+        });
+        assert!(VecEnv::new(game.clone(), 0, [1, 2, 3, 4]).is_err());
+
+        let mut envs = VecEnv::new(game, 3, [1, 2, 3, 4]).unwrap();
+        let first = envs.reset();
+        assert_eq!(first.shape(), &[3, envs.observation_space()]);
+
+        // walking right along the top edge never reaches the food, so every
+        // copy runs out of steps on the fifth and starts again on the same
+        // board
+        let actions = vec![Action::from(Direction::Right); 3];
+        for step in 1..11 {
+            let (observations, _, dones, infos) = envs.step(&actions);
+            let done = step % 5 == 0;
+            for i in 0..3 {
+                assert_eq!(dones[i], done);
+                assert_eq!(infos[i].truncated, done);
+                assert_eq!(infos[i].steps, (step - 1) % 5 + 1);
+                assert_eq!(observations.row(i) == first.row(i), done);
+                if done {
+                    assert!((infos[i].score + 0.5).abs() < 1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Call reset")]
+    fn test_vec_env_step_before_reset() {
+        let game = GameParameters::Game1(Default::default());
+        let mut envs = VecEnv::new(game, 2, [1, 2, 3, 4]).unwrap();
+        envs.step(&[Action(0), Action(0)]);
+    }
+}
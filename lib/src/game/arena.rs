@@ -0,0 +1,323 @@
+use super::*;
+use action::*;
+use SpatiumSys;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+/// Game1 for several agents at once. Every agent is played by its own
+/// network and is rewarded for the food it eats, or for all the food eaten
+/// when `shared_reward` is set.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ArenaParameters {
+    pub max_steps: usize,
+    pub size: usize,
+    pub agents: usize,
+    pub food_count: usize,
+    pub food_reward: f32,
+    /// Cooperate rather than compete.
+    pub shared_reward: bool,
+}
+
+impl Default for ArenaParameters {
+    fn default() -> Self {
+        ArenaParameters {
+            max_steps: 50,
+            size: 8,
+            agents: 2,
+            food_count: 3,
+            food_reward: 10.,
+            shared_reward: false,
+        }
+    }
+}
+
+impl ArenaParameters {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.agents == 0 {
+            return Err("The arena needs at least one agent".into());
+        }
+        if self.food_count == 0 {
+            return Err("foodCount must be at least 1".into());
+        }
+        // eaten food moves elsewhere, so there has to be room for it
+        if self.agents + self.food_count >= self.size * self.size {
+            return Err(format!(
+                "A board of size {} has no room for {} agents and {} food",
+                self.size, self.agents, self.food_count
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub struct Arena {
+    params: ArenaParameters,
+    // lays out the board and picks where eaten food reappears, reseeded
+    // from the episode seed in `reset_agents`
+    rng: XorShiftRng,
+    state: State,
+}
+
+struct State {
+    max_steps: usize,
+    size: usize,
+    step: usize,
+    agents: Vec<(usize, usize)>,
+    food: Vec<(usize, usize)>,
+    food_reward: f32,
+    shared_reward: bool,
+    done: bool,
+    truncated: bool,
+}
+
+impl State {
+    fn new(p: &ArenaParameters, rng: &mut XorShiftRng) -> Self {
+        let mut state = State {
+            max_steps: p.max_steps,
+            size: p.size,
+            step: 0,
+            agents: vec![],
+            food: vec![],
+            food_reward: p.food_reward,
+            shared_reward: p.shared_reward,
+            done: false,
+            truncated: false,
+        };
+        for _ in 0..p.agents {
+            let at = state.random_empty_space(rng);
+            state.agents.push(at);
+        }
+        for _ in 0..p.food_count {
+            let at = state.random_empty_space(rng);
+            state.food.push(at);
+        }
+        state
+    }
+    fn random_empty_space(&self, rng: &mut XorShiftRng) -> (usize, usize) {
+        let free: Vec<_> = (0..self.size)
+            .flat_map(|y| (0..self.size).map(move |x| (x, y)))
+            .filter(|p| !self.agents.contains(p) && !self.food.contains(p))
+            .collect();
+        *rng.choose(&free).expect("No room on the board")
+    }
+    /// The board as agent `i` sees it: itself, the other agents and the food.
+    fn build_state(&self, i: usize) -> GameState {
+        let mut state: ArrayD<u8> = Array::zeros(IxDyn(&[3, self.size, self.size]));
+
+        for (j, &(x, y)) in self.agents.iter().enumerate() {
+            let layer = if i == j { 0 } else { 1 };
+            state[[layer, y, x]] = 1;
+        }
+        for &(x, y) in self.food.iter() {
+            state[[2, y, x]] = 1;
+        }
+
+        GameState::grid(state)
+    }
+    fn update_state(&mut self, rewards: Vec<f32>) -> (Vec<GameState>, Vec<f32>, bool) {
+        self.step += 1;
+        if self.step >= self.max_steps && !self.done {
+            self.done = true;
+            self.truncated = true;
+        }
+
+        let rewards = if self.shared_reward {
+            let total: f32 = rewards.iter().sum();
+            vec![total; rewards.len()]
+        } else {
+            rewards
+        };
+        let states = (0..self.agents.len()).map(|i| self.build_state(i)).collect();
+        (states, rewards, self.done)
+    }
+    fn target(&self, from: (usize, usize), action: &Direction) -> (usize, usize) {
+        let (x, y) = from;
+        match *action {
+            Direction::Up if y > 0 => (x, y - 1),
+            Direction::Right if x < self.size - 1 => (x + 1, y),
+            Direction::Down if y < self.size - 1 => (x, y + 1),
+            Direction::Left if x > 0 => (x - 1, y),
+            _ => from,
+        }
+    }
+    /// Agents move one at a time and can't move onto each other, and those
+    /// without an action stay put. Who goes first rotates every step so no
+    /// agent always gets to the food first.
+    fn step(
+        &mut self,
+        actions: &[Option<Direction>],
+        rng: &mut XorShiftRng,
+    ) -> (Vec<GameState>, Vec<f32>, bool) {
+        if self.done {
+            panic!("Game already done");
+        }
+
+        let n = self.agents.len();
+        let mut rewards = vec![0.; n];
+        for k in 0..n {
+            let i = (self.step + k) % n;
+            let to = match actions[i] {
+                Some(ref action) => self.target(self.agents[i], action),
+                None => continue,
+            };
+            if self.agents.contains(&to) {
+                continue;
+            }
+            self.agents[i] = to;
+
+            if let Some(f) = self.food.iter().position(|food| *food == to) {
+                rewards[i] += self.food_reward;
+                self.food.remove(f);
+                let at = self.random_empty_space(rng);
+                self.food.insert(f, at);
+            }
+        }
+
+        self.update_state(rewards)
+    }
+}
+
+impl Arena {
+    pub fn new(p: ArenaParameters, mut rng: RcRng) -> Box<Game + Send> {
+        let mut game_rng = XorShiftRng::from_seed(rng.seed());
+        let state = State::new(&p, &mut game_rng);
+        let game = Arena {
+            params: p,
+            rng: game_rng,
+            state: state,
+        };
+        Box::new(game)
+    }
+}
+
+impl Game for Arena {
+    fn io(&self) -> (usize, ActionSpace) {
+        (self.state.size * self.state.size * 3, ActionSpace::directions())
+    }
+    fn agents(&self) -> usize {
+        self.params.agents
+    }
+    fn reset(&mut self, rng: RcRng) -> (GameState, f32, bool) {
+        let (mut states, rewards, done) = self.reset_agents(rng);
+        (states.remove(0), rewards[0], done)
+    }
+    /// The other agents stay where they are.
+    fn step(&mut self, _sys: &SpatiumSys, action: &Action) -> (GameState, f32, bool) {
+        let mut directions: Vec<Option<Direction>> = vec![None; self.params.agents];
+        directions[0] = Some(action.into());
+        let (mut states, rewards, done) = self.state.step(&directions, &mut self.rng);
+        (states.remove(0), rewards[0], done)
+    }
+    fn reset_agents(&mut self, mut rng: RcRng) -> (Vec<GameState>, Vec<f32>, bool) {
+        self.rng = XorShiftRng::from_seed(rng.seed());
+        self.state = State::new(&self.params, &mut self.rng);
+        let states = (0..self.params.agents)
+            .map(|i| self.state.build_state(i))
+            .collect();
+        (states, vec![0.; self.params.agents], false)
+    }
+    fn step_agents(
+        &mut self,
+        _sys: &SpatiumSys,
+        actions: &[Action],
+    ) -> (Vec<GameState>, Vec<f32>, bool) {
+        assert_eq!(actions.len(), self.params.agents);
+        let directions: Vec<Option<Direction>> =
+            actions.iter().map(|a| Some(a.into())).collect();
+        self.state.step(&directions, &mut self.rng)
+    }
+    fn truncated(&self) -> bool {
+        self.state.truncated
+    }
+    fn shared_reward(&self) -> bool {
+        self.params.shared_reward
+    }
+    fn rendering_info(&self) -> RenderingInfo {
+        let points = |sprites: &[(usize, usize)]| -> Vec<Point> {
+            sprites
+                .iter()
+                .map(|&(x, y)| Point { x: x, y: y })
+                .collect()
+        };
+        let food_layer = RenderingLayer {
+            name: "food".into(),
+            points: points(&self.state.food),
+        };
+        // the first agent is drawn like the agent in Game1, the rest as rivals
+        let agent_layer = RenderingLayer {
+            name: "agent".into(),
+            points: points(&self.state.agents[..1]),
+        };
+        let rival_layer = RenderingLayer {
+            name: "rival".into(),
+            points: points(&self.state.agents[1..]),
+        };
+
+        RenderingInfo {
+            width: self.state.size,
+            height: self.state.size,
+            layers: vec![food_layer, agent_layer, rival_layer],
+        }
+    }
+    /// Nothing to evaluate, as how well an agent does depends on its
+    /// rivals.
+    fn eval(&self, _sys: &SpatiumSys, _model: &Box<Network + Send>) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn state(shared_reward: bool) -> (State, XorShiftRng) {
+        let p = ArenaParameters {
+            shared_reward: shared_reward,
+            ..Default::default()
+        };
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let mut state = State::new(&p, &mut rng);
+        state.agents = vec![(2, 2), (4, 2)];
+        state.food = vec![(3, 2), (0, 0), (7, 7)];
+        (state, rng)
+    }
+
+    #[test]
+    fn test_compete() {
+        let (mut state, mut rng) = state(false);
+
+        // both go for the same food and the first to move gets it
+        let actions = [Some(Direction::Right), Some(Direction::Left)];
+        let (states, rewards, done) = state.step(&actions, &mut rng);
+        assert_eq!(rewards, vec![10., 0.]);
+        assert!(!done);
+        assert_eq!(state.agents, vec![(3, 2), (4, 2)]);
+        assert_eq!(state.food.len(), 3);
+        assert!(!state.food.contains(&(3, 2)));
+
+        // each agent sees itself on the first layer
+        assert_eq!(states[0].arr[[0, 2, 3]], 1);
+        assert_eq!(states[0].arr[[1, 2, 4]], 1);
+        assert_eq!(states[1].arr[[0, 2, 4]], 1);
+        assert_eq!(states[1].arr[[1, 2, 3]], 1);
+    }
+
+    #[test]
+    fn test_turns_rotate() {
+        let (mut state, mut rng) = state(false);
+        state.step = 1;
+
+        let actions = [Some(Direction::Right), Some(Direction::Left)];
+        let (_, rewards, _) = state.step(&actions, &mut rng);
+        assert_eq!(rewards, vec![0., 10.]);
+        assert_eq!(state.agents, vec![(2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn test_shared_reward() {
+        let (mut state, mut rng) = state(true);
+
+        // the second agent stays put and eats nothing
+        let (_, rewards, _) = state.step(&[Some(Direction::Right), None], &mut rng);
+        assert_eq!(rewards, vec![10., 10.]);
+    }
+}
@@ -0,0 +1,293 @@
+use super::*;
+use action::*;
+use SpatiumSys;
+
+use rand::Rng;
+
+const GRAVITY: f32 = 9.8;
+const CART_MASS: f32 = 1.;
+const POLE_MASS: f32 = 0.1;
+// half the pole's length, in metres
+const POLE_LENGTH: f32 = 0.5;
+const FORCE: f32 = 10.;
+// seconds per step
+const TAU: f32 = 0.02;
+
+// size of the board for rendering
+const WIDTH: usize = 40;
+const HEIGHT: usize = 16;
+const POLE_CELLS: usize = 10;
+
+/// Balance a pole on a cart by pushing the cart left or right. The agent
+/// sees the cart's position and velocity and the pole's angle and angular
+/// velocity, and gets 1 for every step the pole stays up.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CartPoleParameters {
+    pub max_steps: usize,
+    /// The pole falls once it leans further than this, in degrees.
+    pub angle_limit: f32,
+    /// The cart falls off the track once it is further than this from the
+    /// middle, in metres.
+    pub position_limit: f32,
+}
+
+impl Default for CartPoleParameters {
+    fn default() -> Self {
+        CartPoleParameters {
+            max_steps: 200,
+            angle_limit: 12.,
+            position_limit: 2.4,
+        }
+    }
+}
+
+impl CartPoleParameters {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.angle_limit > 0. && self.angle_limit < 90.) {
+            return Err("angleLimit must be between 0 and 90 degrees".into());
+        }
+        if !(self.position_limit > 0.) {
+            return Err("positionLimit must be above 0".into());
+        }
+        Ok(())
+    }
+}
+
+pub struct CartPole {
+    params: CartPoleParameters,
+    state: State,
+}
+
+struct State {
+    max_steps: usize,
+    angle_limit: f32,
+    position_limit: f32,
+    step: usize,
+    x: f32,
+    x_dot: f32,
+    theta: f32,
+    theta_dot: f32,
+    done: bool,
+    truncated: bool,
+}
+
+impl State {
+    fn new(p: &CartPoleParameters, rng: &mut RcRng) -> Self {
+        // start close to upright and still
+        let mut near_zero = || rng.gen_range(-0.05, 0.05);
+        State {
+            max_steps: p.max_steps,
+            angle_limit: p.angle_limit.to_radians(),
+            position_limit: p.position_limit,
+            step: 0,
+            x: near_zero(),
+            x_dot: near_zero(),
+            theta: near_zero(),
+            theta_dot: near_zero(),
+            done: false,
+            truncated: false,
+        }
+    }
+    fn build_state(&self) -> GameState {
+        GameState::from_features(vec![self.x, self.x_dot, self.theta, self.theta_dot])
+    }
+    fn fallen(&self) -> bool {
+        self.x.abs() > self.position_limit || self.theta.abs() > self.angle_limit
+    }
+    fn step(&mut self, push_right: bool) -> (GameState, f32, bool) {
+        if self.done {
+            panic!("Game already done");
+        }
+
+        // Euler integration of the cart and pole equations of motion
+        let force = if push_right { FORCE } else { -FORCE };
+        let total_mass = CART_MASS + POLE_MASS;
+        let pole_mass_length = POLE_MASS * POLE_LENGTH;
+        let (sin, cos) = (self.theta.sin(), self.theta.cos());
+
+        let temp = (force + pole_mass_length * self.theta_dot * self.theta_dot * sin) / total_mass;
+        let theta_acc = (GRAVITY * sin - cos * temp)
+            / (POLE_LENGTH * (4. / 3. - POLE_MASS * cos * cos / total_mass));
+        let x_acc = temp - pole_mass_length * theta_acc * cos / total_mass;
+
+        self.x += TAU * self.x_dot;
+        self.x_dot += TAU * x_acc;
+        self.theta += TAU * self.theta_dot;
+        self.theta_dot += TAU * theta_acc;
+
+        let mut reward = 1.;
+        if self.fallen() {
+            reward = 0.;
+            self.done = true;
+        }
+
+        self.step += 1;
+        if self.step >= self.max_steps && !self.done {
+            self.done = true;
+            self.truncated = true;
+        }
+
+        (self.build_state(), reward, self.done)
+    }
+}
+
+impl CartPole {
+    pub fn new(p: CartPoleParameters, mut rng: RcRng) -> Box<Game + Send> {
+        let state = State::new(&p, &mut rng);
+        let game = CartPole {
+            params: p,
+            state: state,
+        };
+        Box::new(game)
+    }
+}
+
+impl Game for CartPole {
+    fn io(&self) -> (usize, ActionSpace) {
+        (4, ActionSpace::new(&["Left", "Right"]))
+    }
+    fn reset(&mut self, mut rng: RcRng) -> (GameState, f32, bool) {
+        self.state = State::new(&self.params, &mut rng);
+        (self.state.build_state(), 0., false)
+    }
+    fn step(&mut self, _sys: &SpatiumSys, action: &Action) -> (GameState, f32, bool) {
+        match action.0 {
+            0 => self.state.step(false),
+            1 => self.state.step(true),
+            i => panic!(format!("Bad action value: {}", i)),
+        }
+    }
+    fn truncated(&self) -> bool {
+        self.state.truncated
+    }
+    /// The track along the bottom row, the cart just above it and the pole
+    /// as a line of points leaning at its angle.
+    fn rendering_info(&self) -> RenderingInfo {
+        let limit = self.state.position_limit;
+        let x = (self.state.x.max(-limit).min(limit) + limit) / (2. * limit);
+        let cx = (x * (WIDTH - 1) as f32).round() as usize;
+        let cy = HEIGHT - 2;
+
+        let track_layer = RenderingLayer {
+            name: "track".into(),
+            points: (0..WIDTH).map(|x| Point { x: x, y: HEIGHT - 1 }).collect(),
+        };
+        let cart_layer = RenderingLayer {
+            name: "cart".into(),
+            points: (cx.saturating_sub(1)..(cx + 2).min(WIDTH))
+                .map(|x| Point { x: x, y: cy })
+                .collect(),
+        };
+
+        let (sin, cos) = (self.state.theta.sin(), self.state.theta.cos());
+        let pole_layer = RenderingLayer {
+            name: "pole".into(),
+            points: (1..POLE_CELLS + 1)
+                .map(|i| {
+                    let px = cx as f32 + i as f32 * sin;
+                    let py = cy as f32 - i as f32 * cos;
+                    (px.round(), py.round())
+                })
+                .filter(|&(px, py)| px >= 0. && px < WIDTH as f32 && py >= 0.)
+                .map(|(px, py)| Point {
+                    x: px as usize,
+                    y: py as usize,
+                })
+                .collect(),
+        };
+
+        RenderingInfo {
+            width: WIDTH,
+            height: HEIGHT,
+            layers: vec![track_layer, cart_layer, pole_layer],
+        }
+    }
+    fn eval(&self, _sys: &SpatiumSys, _model: &Box<Network + Send>) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use network::{ModelParameters, SingleLayerNetworkParameters};
+    use rand::{weak_rng, SeedableRng, XorShiftRng};
+    use spatium::tests::SpatiumDummy;
+    use Spatium;
+
+    fn rng() -> RcRng {
+        RcRng::new(Box::new(XorShiftRng::from_seed([1, 2, 3, 4])))
+    }
+
+    #[test]
+    fn test_pole_falls() {
+        let mut state = State::new(&Default::default(), &mut rng());
+
+        // pushing one way the whole time tips the pole over the other way
+        let mut steps = 0;
+        loop {
+            let (observation, reward, done) = state.step(true);
+            steps += 1;
+            assert_eq!(observation.features.len(), 4);
+            if done {
+                assert_eq!(reward, 0.);
+                break;
+            }
+            assert_eq!(reward, 1.);
+        }
+        assert!(steps < 50);
+        assert!(state.theta < 0.);
+    }
+
+    #[test]
+    fn test_invalid_limits() {
+        let nan = ::std::f32::NAN;
+        let limits = [(0., 2.4), (90., 2.4), (nan, 2.4), (12., 0.), (12., nan)];
+        for &(angle_limit, position_limit) in limits.iter() {
+            let p = CartPoleParameters {
+                angle_limit: angle_limit,
+                position_limit: position_limit,
+                ..Default::default()
+            };
+            assert!(p.validate().is_err());
+        }
+        assert!(CartPoleParameters::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_rendering_info() {
+        let mut game = CartPole::new(Default::default(), rng());
+        game.reset(rng());
+
+        let info = game.rendering_info();
+        let pole = info.layers.iter().find(|l| l.name == "pole").unwrap();
+        assert_eq!(pole.points.len(), POLE_CELLS);
+        // close to upright in the middle of the track
+        for p in pole.points.iter() {
+            assert!((p.x as isize - (WIDTH / 2) as isize).abs() <= 2);
+        }
+    }
+
+    #[test]
+    fn test_single_layer() {
+        let rng = RcRng::new(Box::new(weak_rng()));
+        let game = GameParameters::CartPole(Default::default());
+        // a small buffer so the network trains on the continuous
+        // observations straight away
+        let model = ModelParameters::QNetwork(SingleLayerNetworkParameters {
+            minibatch_size: 5,
+            expierence_buffer_size: 20,
+            ..Default::default()
+        });
+        let mut spat = Spatium::new(game, model, SpatiumDummy {}, rng.clone(), 5).unwrap();
+
+        let mut episodes = 0;
+        while episodes < 5 {
+            let result = spat.step(rng.clone());
+            assert!(["None", "Left", "Right"].contains(&&result.action[..]));
+            if let Some(ep_result) = result.episode_result {
+                assert!(ep_result.score <= ep_result.steps as f32);
+                episodes += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use super::*;
+use action::*;
+use SpatiumSys;
+
+/// The cliff walking gridworld from Sutton and Barto. The agent starts in
+/// the bottom left corner and has to reach the bottom right one, with a
+/// cliff along the bottom row between them. Every step costs 1 and stepping
+/// off the cliff costs `cliff_penalty` and sends the agent back to the
+/// start.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CliffWalkParameters {
+    pub max_steps: usize,
+    pub width: usize,
+    pub height: usize,
+    pub cliff_penalty: f32,
+}
+
+impl Default for CliffWalkParameters {
+    fn default() -> Self {
+        CliffWalkParameters {
+            max_steps: 100,
+            width: 12,
+            height: 4,
+            cliff_penalty: 100.,
+        }
+    }
+}
+
+impl CliffWalkParameters {
+    pub fn validate(&self) -> Result<(), String> {
+        // the cliff needs at least one square, and a row above to walk along
+        if self.width < 3 || self.height < 2 {
+            return Err("The cliff walk must be at least 3 wide and 2 high".into());
+        }
+        if self.cliff_penalty.is_nan() {
+            return Err("cliffPenalty must be a number".into());
+        }
+        Ok(())
+    }
+}
+
+pub struct CliffWalk {
+    params: CliffWalkParameters,
+    state: State,
+}
+
+struct State {
+    max_steps: usize,
+    width: usize,
+    height: usize,
+    cliff_penalty: f32,
+    step: usize,
+    agent: (usize, usize),
+    done: bool,
+    truncated: bool,
+}
+
+impl State {
+    fn new(p: &CliffWalkParameters) -> Self {
+        State {
+            max_steps: p.max_steps,
+            width: p.width,
+            height: p.height,
+            cliff_penalty: p.cliff_penalty,
+            step: 0,
+            agent: State::start(p.height),
+            done: false,
+            truncated: false,
+        }
+    }
+    fn start(height: usize) -> (usize, usize) {
+        (0, height - 1)
+    }
+    fn goal(&self) -> (usize, usize) {
+        (self.width - 1, self.height - 1)
+    }
+    fn is_cliff(&self, x: usize, y: usize) -> bool {
+        y == self.height - 1 && x > 0 && x < self.width - 1
+    }
+    fn build_state(&self) -> GameState {
+        let mut state: ArrayD<u8> = Array::zeros(IxDyn(&[1, self.height, self.width]));
+        state[[0, self.agent.1, self.agent.0]] = 1;
+        GameState::grid(state)
+    }
+    fn step(&mut self, action: &Direction) -> (GameState, f32, bool) {
+        if self.done {
+            panic!("Game already done");
+        }
+
+        let (x, y) = self.agent;
+        self.agent = match *action {
+            Direction::Up if y > 0 => (x, y - 1),
+            Direction::Right if x < self.width - 1 => (x + 1, y),
+            Direction::Down if y < self.height - 1 => (x, y + 1),
+            Direction::Left if x > 0 => (x - 1, y),
+            _ => self.agent,
+        };
+
+        let mut reward = -1.;
+        if self.is_cliff(self.agent.0, self.agent.1) {
+            // the episode carries on from the start
+            reward = -self.cliff_penalty;
+            self.agent = State::start(self.height);
+        } else if self.agent == self.goal() {
+            self.done = true;
+        }
+
+        self.step += 1;
+        if self.step >= self.max_steps && !self.done {
+            self.done = true;
+            self.truncated = true;
+        }
+
+        (self.build_state(), reward, self.done)
+    }
+}
+
+impl CliffWalk {
+    pub fn new(p: CliffWalkParameters, _rng: RcRng) -> Box<Game + Send> {
+        let state = State::new(&p);
+        let game = CliffWalk {
+            params: p,
+            state: state,
+        };
+        Box::new(game)
+    }
+}
+
+impl Game for CliffWalk {
+    fn io(&self) -> (usize, ActionSpace) {
+        let inputs = self.params.width * self.params.height;
+        (inputs, ActionSpace::directions())
+    }
+    fn reset(&mut self, _rng: RcRng) -> (GameState, f32, bool) {
+        self.state = State::new(&self.params);
+        (self.state.build_state(), 0., false)
+    }
+    fn step(&mut self, _sys: &SpatiumSys, action: &Action) -> (GameState, f32, bool) {
+        let direction: Direction = action.into();
+        self.state.step(&direction)
+    }
+    fn truncated(&self) -> bool {
+        self.state.truncated
+    }
+    fn rendering_info(&self) -> RenderingInfo {
+        let (width, height) = (self.state.width, self.state.height);
+
+        let cliff_layer = RenderingLayer {
+            name: "cliff".into(),
+            points: (1..width - 1).map(|x| Point { x: x, y: height - 1 }).collect(),
+        };
+        let (gx, gy) = self.state.goal();
+        let goal_layer = RenderingLayer {
+            name: "exit".into(),
+            points: vec![Point { x: gx, y: gy }],
+        };
+        let agent_layer = RenderingLayer {
+            name: "agent".into(),
+            points: vec![
+                Point {
+                    x: self.state.agent.0,
+                    y: self.state.agent.1,
+                },
+            ],
+        };
+
+        RenderingInfo {
+            width: width,
+            height: height,
+            layers: vec![cliff_layer, goal_layer, agent_layer],
+        }
+    }
+    fn eval(&self, _sys: &SpatiumSys, _model: &Box<Network + Send>) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cliff() {
+        let mut state = State::new(&Default::default());
+
+        let (_, reward, done) = state.step(&Direction::Right);
+        assert_eq!(reward, -100.);
+        assert!(!done);
+        assert_eq!(state.agent, (0, 3));
+
+        // along the top of the cliff and down to the goal
+        let mut score = 0.;
+        let mut path = vec![Direction::Up];
+        path.extend(vec![Direction::Right; 11]);
+        path.push(Direction::Down);
+        for (i, direction) in path.iter().enumerate() {
+            let (_, reward, done) = state.step(direction);
+            score += reward;
+            assert_eq!(done, i == path.len() - 1);
+        }
+        assert_eq!(score, -13.);
+    }
+
+    #[test]
+    fn test_invalid_penalty() {
+        let p = CliffWalkParameters {
+            cliff_penalty: ::std::f32::NAN,
+            ..Default::default()
+        };
+        assert!(p.validate().is_err());
+    }
+}
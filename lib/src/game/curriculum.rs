@@ -0,0 +1,225 @@
+use std::collections::VecDeque;
+
+/// How the level changes from one episode to the next.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CurriculumStrategy {
+    /// Up a level after every success and down one after every failure,
+    /// but never below level 3.
+    Adaptive,
+    /// Stay on the starting level.
+    Fixed,
+    /// Up a level once the success rate over the last `window` episodes
+    /// reaches `success_rate`.
+    Threshold {
+        window: usize,
+        #[serde(rename = "successRate")]
+        success_rate: f32,
+    },
+    /// Up a level every `episodes_per_level` episodes, whatever the results.
+    Linear {
+        #[serde(rename = "episodesPerLevel")]
+        episodes_per_level: usize,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CurriculumParameters {
+    pub strategy: CurriculumStrategy,
+    pub start_level: usize,
+    /// No limit when left out.
+    pub max_level: Option<usize>,
+}
+
+impl Default for CurriculumParameters {
+    fn default() -> Self {
+        CurriculumParameters {
+            strategy: CurriculumStrategy::Adaptive,
+            start_level: 1,
+            max_level: None,
+        }
+    }
+}
+
+impl CurriculumParameters {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.start_level == 0 {
+            return Err("startLevel must be at least 1".into());
+        }
+        if let Some(max_level) = self.max_level {
+            if max_level < self.start_level {
+                return Err("maxLevel can't be below startLevel".into());
+            }
+        }
+        match self.strategy {
+            CurriculumStrategy::Threshold {
+                window,
+                success_rate,
+            } => {
+                if window == 0 {
+                    return Err("The curriculum window must be at least 1".into());
+                }
+                if !(success_rate >= 0. && success_rate <= 1.) {
+                    return Err("successRate must be between 0 and 1".into());
+                }
+            }
+            CurriculumStrategy::Linear { episodes_per_level } => {
+                if episodes_per_level == 0 {
+                    return Err("episodesPerLevel must be at least 1".into());
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+// the adaptive strategy never drops below this level
+const ADAPTIVE_FLOOR: usize = 3;
+
+/// Tracks the level a game is played at.
+pub struct Curriculum {
+    params: CurriculumParameters,
+    level: usize,
+    episodes: usize,
+    // results of the episodes played since the last level up
+    results: VecDeque<bool>,
+}
+
+impl Curriculum {
+    pub fn new(params: CurriculumParameters) -> Self {
+        let level = params.start_level;
+        Curriculum {
+            params: params,
+            level: level,
+            episodes: 0,
+            results: VecDeque::new(),
+        }
+    }
+    pub fn level(&self) -> usize {
+        self.level
+    }
+    /// Jump straight to `level`, as when replaying an episode.
+    pub fn set_level(&mut self, level: usize) {
+        self.level = level;
+        self.results.clear();
+    }
+    /// Record the result of an episode and move to the level for the next
+    /// one.
+    pub fn episode_done(&mut self, success: bool) {
+        self.episodes += 1;
+
+        let level = match self.params.strategy {
+            CurriculumStrategy::Adaptive => {
+                if success {
+                    self.level + 1
+                } else if self.level > ADAPTIVE_FLOOR {
+                    self.level - 1
+                } else {
+                    self.level
+                }
+            }
+            CurriculumStrategy::Fixed => self.level,
+            CurriculumStrategy::Threshold {
+                window,
+                success_rate,
+            } => {
+                self.results.push_back(success);
+                if self.results.len() > window {
+                    self.results.pop_front();
+                }
+
+                let successes = self.results.iter().filter(|s| **s).count();
+                let rate = successes as f32 / window as f32;
+                if self.results.len() == window && rate >= success_rate {
+                    self.results.clear();
+                    self.level + 1
+                } else {
+                    self.level
+                }
+            }
+            CurriculumStrategy::Linear { episodes_per_level } => {
+                self.params.start_level + self.episodes / episodes_per_level
+            }
+        };
+
+        self.level = match self.params.max_level {
+            Some(max_level) => level.min(max_level),
+            None => level,
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn curriculum(strategy: CurriculumStrategy) -> Curriculum {
+        Curriculum::new(CurriculumParameters {
+            strategy: strategy,
+            start_level: 2,
+            max_level: Some(4),
+        })
+    }
+
+    #[test]
+    fn test_adaptive() {
+        let mut c = curriculum(CurriculumStrategy::Adaptive);
+        c.episode_done(false);
+        assert_eq!(c.level(), 2);
+        c.episode_done(true);
+        c.episode_done(true);
+        c.episode_done(true);
+        assert_eq!(c.level(), 4);
+        c.episode_done(false);
+        assert_eq!(c.level(), 3);
+    }
+
+    #[test]
+    fn test_threshold() {
+        let mut c = curriculum(CurriculumStrategy::Threshold {
+            window: 3,
+            success_rate: 0.6,
+        });
+        for success in &[true, false] {
+            c.episode_done(*success);
+        }
+        assert_eq!(c.level(), 2);
+        c.episode_done(true);
+        assert_eq!(c.level(), 3);
+
+        // the window starts over on the new level
+        c.episode_done(true);
+        c.episode_done(true);
+        assert_eq!(c.level(), 3);
+    }
+
+    #[test]
+    fn test_linear() {
+        let mut c = curriculum(CurriculumStrategy::Linear {
+            episodes_per_level: 2,
+        });
+        let levels: Vec<_> = (0..6)
+            .map(|_| {
+                c.episode_done(false);
+                c.level()
+            })
+            .collect();
+        assert_eq!(levels, vec![2, 3, 3, 4, 4, 4]);
+    }
+
+    #[test]
+    fn test_invalid_success_rate() {
+        for &rate in &[-0.1, 1.1, ::std::f32::NAN] {
+            let p = CurriculumParameters {
+                strategy: CurriculumStrategy::Threshold {
+                    window: 3,
+                    success_rate: rate,
+                },
+                ..Default::default()
+            };
+            assert!(p.validate().is_err());
+        }
+    }
+}
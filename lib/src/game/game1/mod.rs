@@ -0,0 +1,1234 @@
+use super::*;
+use action::*;
+use SpatiumSys;
+
+use std::collections::VecDeque;
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+pub mod planner;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sprite {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Sprite {
+    fn touches(&self, other: &Sprite) -> bool {
+        self.x == other.x && self.y == other.y
+    }
+}
+
+fn sprite(x: usize, y: usize) -> Sprite {
+    Sprite { x: x, y: y }
+}
+
+/// How hazards move after every agent step.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum HazardPolicy {
+    /// A random direction each step.
+    RandomWalk,
+    /// Back and forth along a line, turning around when blocked.
+    Patrol,
+    /// Greedily towards the agent.
+    Chase,
+}
+
+struct Hazard {
+    at: Sprite,
+    heading: Direction,
+}
+
+/// What the agent gets to see of the board.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Observation {
+    /// The whole board, one layer for the agent and one for each kind of
+    /// sprite that can move between episodes.
+    Full,
+    /// A `size` by `size` window centered on the agent, with layers for
+    /// walls, food and hazards. Squares off the board count as walls.
+    Local { size: usize },
+    /// The agent's position followed by the offset to the nearest food and
+    /// hazard, scaled by the board size.
+    Coordinates,
+    /// Looking up, right, down and left from the agent: how far away the
+    /// nearest wall is and how close any food or hazard in sight is, scaled
+    /// by the board size.
+    Rays,
+}
+
+fn distance(a: &Sprite, b: &Sprite) -> usize {
+    let dx = if a.x > b.x { a.x - b.x } else { b.x - a.x };
+    let dy = if a.y > b.y { a.y - b.y } else { b.y - a.y };
+    dx + dy
+}
+
+// random layouts are redrawn until all the food can be reached
+const MAX_LAYOUT_ATTEMPTS: usize = 100;
+
+/// A board read from `Game1Parameters::map`.
+struct Map {
+    agent: Option<Sprite>,
+    food: Vec<Sprite>,
+    blocks: Vec<Sprite>,
+}
+
+/// Parse a map with one row per line, using `#` for blocks, `A` for the
+/// agent, `F` for food and `.` for empty squares. Blank lines and
+/// surrounding whitespace are ignored.
+fn parse_map(map: &str, size: usize) -> Result<Map, String> {
+    let rows: Vec<&str> = map.lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect();
+    if rows.len() != size {
+        return Err(format!(
+            "Map has {} rows but the board size is {}",
+            rows.len(),
+            size
+        ));
+    }
+
+    let mut parsed = Map {
+        agent: None,
+        food: vec![],
+        blocks: vec![],
+    };
+    let mut empty = 0;
+
+    for (y, row) in rows.iter().enumerate() {
+        let len = row.chars().count();
+        if len != size {
+            return Err(format!(
+                "Map row {} has {} columns but the board size is {}",
+                y + 1,
+                len,
+                size
+            ));
+        }
+        for (x, c) in row.chars().enumerate() {
+            match c {
+                '#' => parsed.blocks.push(sprite(x, y)),
+                'F' => parsed.food.push(sprite(x, y)),
+                '.' => empty += 1,
+                'A' => {
+                    if parsed.agent.is_some() {
+                        return Err("Map has more than one agent".into());
+                    }
+                    parsed.agent = Some(sprite(x, y));
+                }
+                c => return Err(format!("Unknown map character '{}' at ({}, {})", c, x, y)),
+            }
+        }
+    }
+
+    // whatever the map leaves out is placed on an empty square
+    let mut missing = 0;
+    if parsed.agent.is_none() {
+        missing += 1;
+    }
+    if parsed.food.is_empty() {
+        missing += 1;
+    }
+    if empty < missing {
+        return Err("Map has no room left for the agent and food".into());
+    }
+
+    Ok(parsed)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Game1Parameters {
+    pub max_steps: usize,
+    pub size: usize,
+    pub random: bool,
+    /// Optional fixed layout, see `parse_map` for the format. Blocks always
+    /// come from the map, while the agent and food are only placed at random
+    /// when the map leaves them out.
+    pub map: Option<String>,
+    /// Blocks placed at random anywhere on the board, on top of the fixed
+    /// ones.
+    pub random_blocks: usize,
+    /// Number of food items when food is placed at random.
+    pub food_count: usize,
+    pub food_reward: f32,
+    /// Keep going until every food item is eaten instead of ending the
+    /// episode on the first one.
+    pub eat_all: bool,
+    /// Number of moving hazards. Touching one ends the episode.
+    pub hazards: usize,
+    pub hazard_policy: HazardPolicy,
+    /// Taken off the reward on contact with a hazard.
+    pub hazard_penalty: f32,
+    /// Taken off the reward on every step.
+    pub step_penalty: f32,
+    /// Taken off the reward when a move is blocked by a block or the edge
+    /// of the board.
+    pub wall_penalty: f32,
+    /// Chance of the agent slipping and moving at right angles to the chosen
+    /// direction instead, either way being equally likely.
+    pub slip: f32,
+    pub observation: Observation,
+    /// Higher levels spawn the agent and food further apart.
+    pub curriculum: CurriculumParameters,
+}
+
+impl Default for Game1Parameters {
+    fn default() -> Self {
+        let size = 3;
+        Game1Parameters {
+            max_steps: 30,
+            size: size,
+            random: true,
+            map: None,
+            random_blocks: 0,
+            food_count: 1,
+            food_reward: 10.,
+            eat_all: false,
+            hazards: 0,
+            hazard_policy: HazardPolicy::RandomWalk,
+            hazard_penalty: 10.,
+            step_penalty: 0.,
+            wall_penalty: 0.,
+            slip: 0.,
+            observation: Observation::Full,
+            curriculum: Default::default(),
+        }
+    }
+}
+
+impl Game1Parameters {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.size < 2 {
+            return Err("The board must be at least 2 wide".into());
+        }
+        let layout = self.layout()?;
+        if self.food_count == 0 {
+            return Err("foodCount must be at least 1".into());
+        }
+        if !(self.slip >= 0. && self.slip <= 1.) {
+            return Err("slip must be between 0 and 1".into());
+        }
+        self.curriculum.validate()?;
+        if let Observation::Local { size } = self.observation {
+            if size % 2 == 0 {
+                return Err("A local view needs an odd size to center on the agent".into());
+            }
+        }
+
+        // the corners the agent and food default to can be taken
+        if let Some(ref agent) = layout.agent {
+            let mut taken = layout.food.iter().chain(layout.blocks.iter());
+            if taken.any(|s| s.touches(agent)) {
+                return Err(format!(
+                    "The agent's square ({}, {}) is already taken",
+                    agent.x, agent.y
+                ));
+            }
+        }
+        for food in layout.food.iter() {
+            if layout.blocks.iter().any(|b| b.touches(food)) {
+                return Err(format!("The food's square ({}, {}) is a block", food.x, food.y));
+            }
+        }
+
+        // every food item on the map is placed, whatever `food_count` says
+        let food = if layout.food.is_empty() {
+            self.food_count
+        } else {
+            layout.food.len()
+        };
+        let fixed = layout.blocks.len();
+        let placed = self.random_blocks + food + self.hazards + 1;
+        if fixed + placed > self.size * self.size {
+            return Err(format!(
+                "A {0}x{0} board has no room for {1} random blocks, {2} food and {3} hazards",
+                self.size, self.random_blocks, food, self.hazards
+            ));
+        }
+
+        // layouts with random blocks fall back to the fixed blocks alone, so
+        // those have to leave the food reachable from wherever the agent
+        // starts
+        let fully_fixed = layout.agent.is_some() && !layout.food.is_empty();
+        let mut state = State::empty(self);
+        state.blocks = layout.blocks;
+        let free: Vec<Sprite> = (0..self.size * self.size)
+            .map(|i| sprite(i % self.size, i / self.size))
+            .filter(|s| !state.blocks.iter().any(|b| b.touches(s)))
+            .collect();
+        state.agent = layout.agent.unwrap_or_else(|| free[0].clone());
+        state.food = if fully_fixed { layout.food } else { free };
+        if !state.food_reachable() {
+            return Err(if fully_fixed {
+                "Map has food the agent cannot reach".into()
+            } else {
+                "Map has empty squares cut off from the rest".into()
+            });
+        }
+        Ok(())
+    }
+    /// The fixed part of the board. Without a map there is a single block
+    /// at (1, 1). When `random` is off, an agent or food the map leaves out
+    /// goes in the top left or bottom right corner.
+    fn layout(&self) -> Result<Map, String> {
+        let mut layout = match self.map {
+            Some(ref map) => parse_map(map, self.size)?,
+            None => Map {
+                agent: None,
+                food: vec![],
+                blocks: vec![sprite(1, 1)],
+            },
+        };
+        if !self.random {
+            if layout.agent.is_none() {
+                layout.agent = Some(sprite(0, 0));
+            }
+            if layout.food.is_empty() {
+                layout.food.push(sprite(self.size - 1, self.size - 1));
+            }
+        }
+        Ok(layout)
+    }
+    // food is only part of the observation when it can change
+    fn show_food(&self) -> bool {
+        self.random || self.eat_all
+    }
+    fn show_blocks(&self) -> bool {
+        self.random_blocks > 0
+    }
+    fn show_hazards(&self) -> bool {
+        self.hazards > 0
+    }
+    // length of a flattened observation
+    fn inputs(&self) -> usize {
+        match self.observation {
+            Observation::Full => {
+                let mut layers = 1;
+                if self.show_food() {
+                    layers += 1;
+                }
+                if self.show_blocks() {
+                    layers += 1;
+                }
+                if self.show_hazards() {
+                    layers += 1;
+                }
+                self.size * self.size * layers
+            }
+            Observation::Local { size } => {
+                let mut layers = 2;
+                if self.show_hazards() {
+                    layers += 1;
+                }
+                size * size * layers
+            }
+            Observation::Coordinates => {
+                if self.show_hazards() {
+                    6
+                } else {
+                    4
+                }
+            }
+            Observation::Rays => {
+                if self.show_hazards() {
+                    12
+                } else {
+                    8
+                }
+            }
+        }
+    }
+}
+
+pub struct Game1 {
+    params: Game1Parameters,
+    // the map, parsed once when the game is created
+    layout: Map,
+    curriculum: Curriculum,
+    // hazards move mid episode, so the game keeps its own rng seeded on reset
+    rng: XorShiftRng,
+    state: State,
+}
+
+struct State {
+    max_steps: usize,
+    width: usize,
+    height: usize,
+    show_food: bool,
+    show_blocks: bool,
+    show_hazards: bool,
+    food_reward: f32,
+    eat_all: bool,
+    hazard_policy: HazardPolicy,
+    hazard_penalty: f32,
+    step_penalty: f32,
+    wall_penalty: f32,
+    slip: f32,
+    observation: Observation,
+    step: usize,
+    agent: Sprite,
+    blocks: Vec<Sprite>,
+    food: Vec<Sprite>,
+    hazards: Vec<Hazard>,
+    // reward for the latest step
+    reward: f32,
+    eaten: usize,
+    done: bool,
+    truncated: bool,
+}
+
+impl State {
+    /// The agent in the corner with no food or blocks.
+    fn empty(p: &Game1Parameters) -> Self {
+        State {
+            max_steps: p.max_steps,
+            width: p.size,
+            height: p.size,
+            show_food: p.show_food(),
+            show_blocks: p.show_blocks(),
+            show_hazards: p.show_hazards(),
+            food_reward: p.food_reward,
+            eat_all: p.eat_all,
+            hazard_policy: p.hazard_policy,
+            hazard_penalty: p.hazard_penalty,
+            step_penalty: p.step_penalty,
+            wall_penalty: p.wall_penalty,
+            slip: p.slip,
+            observation: p.observation.clone(),
+            step: 0,
+            agent: sprite(0, 0),
+            blocks: vec![],
+            food: vec![],
+            hazards: vec![],
+            reward: 0.,
+            eaten: 0,
+            done: false,
+            truncated: false,
+        }
+    }
+    fn new(p: &Game1Parameters, layout: &Map, level: usize, rng: &mut RcRng) -> Self {
+        for _ in 0..MAX_LAYOUT_ATTEMPTS {
+            if let Some(state) = State::place(p, layout, p.random_blocks, level, rng) {
+                if state.food_reachable() {
+                    return state;
+                }
+            }
+        }
+        // validation checked the fixed blocks leave room and keep all the
+        // food reachable, so this layout always works
+        State::place(p, layout, 0, level, rng).expect("No room on the board")
+    }
+    fn place(
+        p: &Game1Parameters,
+        layout: &Map,
+        random_blocks: usize,
+        level: usize,
+        rng: &mut RcRng,
+    ) -> Option<Self> {
+        let mut state = State::empty(p);
+        state.blocks = layout.blocks.clone();
+
+        // fixed positions go first so nothing random lands on them
+        if let Some(ref agent) = layout.agent {
+            state.agent = agent.clone();
+        }
+        state.food = layout.food.clone();
+
+        for _ in 0..random_blocks {
+            let width = state.width;
+            let block = state.random_empty_space(rng, width)?;
+            state.blocks.push(block);
+        }
+
+        if layout.agent.is_none() {
+            state.agent = state.random_empty_space(rng, level)?;
+        }
+
+        if layout.food.is_empty() {
+            for _ in 0..p.food_count {
+                let food = state.random_empty_space(rng, level)?;
+                state.food.push(food);
+            }
+        }
+
+        let actions = Direction::all();
+        for _ in 0..p.hazards {
+            let width = state.width;
+            let at = state.random_empty_space(rng, width)?;
+            let heading = *rng.choose(&actions).unwrap();
+            state.hazards.push(Hazard {
+                at: at,
+                heading: heading,
+            });
+        }
+
+        Some(state)
+    }
+    // breadth first search from the agent's position
+    fn food_reachable(&self) -> bool {
+        let mut seen = vec![false; self.width * self.height];
+        let mut queue = VecDeque::new();
+        seen[self.agent.y * self.width + self.agent.x] = true;
+        queue.push_back(self.agent.clone());
+
+        while let Some(at) = queue.pop_front() {
+            for action in &Direction::all() {
+                let next = self.move_from(&at, action);
+                let i = next.y * self.width + next.x;
+                if !seen[i] {
+                    seen[i] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        self.food.iter().all(|f| seen[f.y * self.width + f.x])
+    }
+    fn is_free(&self, sprite: &Sprite) -> bool {
+        if sprite.touches(&self.agent) {
+            return false;
+        }
+        let food = self.food.iter();
+        let blocks = self.blocks.iter();
+        let hazards = self.hazards.iter().map(|h| &h.at);
+        for s in food.chain(blocks).chain(hazards) {
+            if sprite.touches(s) {
+                return false;
+            }
+        }
+        true
+    }
+    fn has_free_space(&self, width: usize, height: usize) -> bool {
+        (0..height).any(|y| (0..width).any(|x| self.is_free(&sprite(x, y))))
+    }
+    /// A random free square within `level + 1` of the top left corner, or
+    /// anywhere on the board when that is full. `None` when the whole board is full.
+    fn random_empty_space(&self, rng: &mut RcRng, level: usize) -> Option<Sprite> {
+        let level = level + 1;
+        let mut width = level.min(self.width);
+        let mut height = level.min(self.height);
+
+        // a map can fill the spawn area, so fall back to the whole board
+        if !self.has_free_space(width, height) {
+            width = self.width;
+            height = self.height;
+            if !self.has_free_space(width, height) {
+                return None;
+            }
+        }
+
+        use rand::distributions::{IndependentSample, Range};
+        let width_range = Range::new(0, width);
+        let height_range = Range::new(0, height);
+
+        loop {
+            let sprite = Sprite {
+                x: width_range.ind_sample(rng),
+                y: height_range.ind_sample(rng),
+            };
+            if self.is_free(&sprite) {
+                return Some(sprite);
+            }
+        }
+    }
+    fn build_state(&self) -> GameState {
+        match self.observation {
+            Observation::Full => self.full_view(),
+            Observation::Local { size } => self.local_view(size),
+            Observation::Coordinates => GameState::from_features(self.coordinates()),
+            Observation::Rays => GameState::from_features(self.rays()),
+        }
+    }
+    fn full_view(&self) -> GameState {
+        let mut layers = 1;
+        if self.show_food {
+            layers += 1;
+        }
+        if self.show_blocks {
+            layers += 1;
+        }
+        if self.show_hazards {
+            layers += 1;
+        }
+        let mut state: ArrayD<u8> = Array::zeros(IxDyn(&[layers, self.height, self.width]));
+
+        let mut idx = 0;
+        state[[idx, self.agent.y, self.agent.x]] = 1;
+        if self.show_food {
+            idx += 1;
+            for sprite in &self.food {
+                state[[idx, sprite.y, sprite.x]] = 1;
+            }
+        }
+        if self.show_blocks {
+            idx += 1;
+            for sprite in &self.blocks {
+                state[[idx, sprite.y, sprite.x]] = 1;
+            }
+        }
+        if self.show_hazards {
+            idx += 1;
+            for hazard in &self.hazards {
+                state[[idx, hazard.at.y, hazard.at.x]] = 1;
+            }
+        }
+
+        GameState::grid(state)
+    }
+    fn local_view(&self, size: usize) -> GameState {
+        let mut layers = 2;
+        if self.show_hazards {
+            layers += 1;
+        }
+        let mut state: ArrayD<u8> = Array::zeros(IxDyn(&[layers, size, size]));
+
+        let radius = (size / 2) as isize;
+        for vy in 0..size {
+            for vx in 0..size {
+                let x = self.agent.x as isize + vx as isize - radius;
+                let y = self.agent.y as isize + vy as isize - radius;
+                if x < 0 || y < 0 || x >= self.width as isize || y >= self.height as isize {
+                    state[[0, vy, vx]] = 1;
+                    continue;
+                }
+
+                let at = sprite(x as usize, y as usize);
+                if self.blocks.iter().any(|s| s.touches(&at)) {
+                    state[[0, vy, vx]] = 1;
+                }
+                if self.food.iter().any(|s| s.touches(&at)) {
+                    state[[1, vy, vx]] = 1;
+                }
+                if self.show_hazards && self.hazards.iter().any(|h| h.at.touches(&at)) {
+                    state[[2, vy, vx]] = 1;
+                }
+            }
+        }
+
+        GameState::grid(state)
+    }
+    fn coordinates(&self) -> Vec<f32> {
+        let scale = (self.width.max(self.height) - 1).max(1) as f32;
+        let agent = &self.agent;
+        let offset = |sprites: Vec<&Sprite>| match sprites
+            .into_iter()
+            .min_by_key(|s| distance(s, agent))
+        {
+            Some(s) => vec![
+                (s.x as f32 - agent.x as f32) / scale,
+                (s.y as f32 - agent.y as f32) / scale,
+            ],
+            // all the food has been eaten
+            None => vec![0., 0.],
+        };
+
+        let mut features = vec![agent.x as f32 / scale, agent.y as f32 / scale];
+        features.extend(offset(self.food.iter().collect()));
+        if self.show_hazards {
+            features.extend(offset(self.hazards.iter().map(|h| &h.at).collect()));
+        }
+        features
+    }
+    fn rays(&self) -> Vec<f32> {
+        let scale = self.width.max(self.height) as f32;
+        let mut features = vec![];
+
+        for direction in &Direction::all() {
+            let mut at = self.agent.clone();
+            let mut steps = 0;
+            let mut food = 0.;
+            let mut hazard = 0.;
+            loop {
+                let next = self.move_from(&at, direction);
+                if next == at {
+                    break;
+                }
+                at = next;
+                steps += 1;
+
+                let closeness = 1. - (steps - 1) as f32 / scale;
+                if food == 0. && self.food.iter().any(|s| s.touches(&at)) {
+                    food = closeness;
+                }
+                if hazard == 0. && self.hazards.iter().any(|h| h.at.touches(&at)) {
+                    hazard = closeness;
+                }
+            }
+
+            features.push(steps as f32 / scale);
+            features.push(food);
+            if self.show_hazards {
+                features.push(hazard);
+            }
+        }
+        features
+    }
+    fn hit_hazard(&self) -> bool {
+        self.hazards.iter().any(|h| h.at.touches(&self.agent))
+    }
+    fn on_food(&self) -> bool {
+        self.food.iter().any(|f| f.touches(&self.agent))
+    }
+    fn move_hazards(&mut self, rng: &mut XorShiftRng) {
+        let actions = Direction::all();
+        for i in 0..self.hazards.len() {
+            let (at, heading) = {
+                let hazard = &self.hazards[i];
+                match self.hazard_policy {
+                    HazardPolicy::RandomWalk => {
+                        let action = *rng.choose(&actions).unwrap();
+                        (self.move_from(&hazard.at, &action), action)
+                    }
+                    HazardPolicy::Patrol => {
+                        let next = self.move_from(&hazard.at, &hazard.heading);
+                        if next == hazard.at {
+                            let back = hazard.heading.opposite();
+                            (self.move_from(&hazard.at, &back), back)
+                        } else {
+                            (next, hazard.heading)
+                        }
+                    }
+                    HazardPolicy::Chase => actions
+                        .iter()
+                        .map(|a| (self.move_from(&hazard.at, a), *a))
+                        .min_by_key(|&(ref next, _)| distance(next, &self.agent))
+                        .unwrap(),
+                }
+            };
+            self.hazards[i].at = at;
+            self.hazards[i].heading = heading;
+        }
+    }
+    fn update_state(&mut self) -> (GameState, f32, bool) {
+        let agent = self.agent.clone();
+        if self.hit_hazard() {
+            self.reward -= self.hazard_penalty;
+            self.done = true;
+        } else if self.food.iter().any(|f| f.touches(&agent)) {
+            self.reward += self.food_reward;
+            self.eaten += 1;
+            if self.eat_all {
+                self.food.retain(|f| !f.touches(&agent));
+                if self.food.is_empty() {
+                    self.done = true;
+                }
+            } else {
+                self.done = true;
+            }
+        }
+
+        self.step += 1;
+        if self.step >= self.max_steps && !self.done {
+            self.done = true;
+            self.truncated = true;
+        }
+
+        (self.build_state(), self.reward, self.done)
+    }
+    // where the agent ends up if it takes the action, staying put when
+    // moving off the board or into a block
+    fn target(&self, action: &Direction) -> Sprite {
+        self.move_from(&self.agent, action)
+    }
+    fn move_from(&self, from: &Sprite, action: &Direction) -> Sprite {
+        let mut new_x = from.x;
+        let mut new_y = from.y;
+
+        match *action {
+            Direction::Up => {
+                if from.y <= 0 {
+                    return from.clone();
+                }
+                new_y -= 1;
+            }
+            Direction::Right => {
+                if from.x >= self.width - 1 {
+                    return from.clone();
+                }
+                new_x += 1;
+            }
+            Direction::Down => {
+                if from.y >= self.height - 1 {
+                    return from.clone();
+                }
+                new_y += 1;
+            }
+            Direction::Left => {
+                if from.x <= 0 {
+                    return from.clone();
+                }
+                new_x -= 1;
+            }
+        }
+
+        // unable to move
+        for block in &self.blocks {
+            if block.x == new_x && block.y == new_y {
+                return from.clone();
+            }
+        }
+
+        sprite(new_x, new_y)
+    }
+    fn step(
+        &mut self,
+        _sys: &SpatiumSys,
+        action: &Direction,
+        rng: &mut XorShiftRng,
+    ) -> (GameState, f32, bool) {
+        if self.done {
+            panic!("Game already done");
+        }
+
+        // sys.debug(&format!("Game step {} to {}", self.step, self.step + 1));
+
+        self.reward = -self.step_penalty;
+
+        let mut action = *action;
+        if self.slip > 0. && rng.next_f32() < self.slip {
+            action = *rng.choose(&action.perpendicular()).unwrap();
+        }
+
+        let target = self.target(&action);
+        if target == self.agent {
+            self.reward -= self.wall_penalty;
+        }
+        self.agent = target;
+
+        // hazards only get to move when the agent survived its own move
+        if !self.hit_hazard() && !self.on_food() {
+            self.move_hazards(rng);
+        }
+
+        self.update_state()
+    }
+}
+
+impl Game1 {
+    pub fn new(p: Game1Parameters, mut rng: RcRng) -> Box<Game + Send> {
+        let layout = p.layout().expect("invalid map");
+        let curriculum = Curriculum::new(p.curriculum.clone());
+        let state = State::new(&p, &layout, curriculum.level(), &mut rng);
+        let game = Self {
+            params: p,
+            layout: layout,
+            curriculum: curriculum,
+            rng: XorShiftRng::from_seed(rng.seed()),
+            state: state,
+        };
+        Box::new(game)
+    }
+}
+
+impl Game for Game1 {
+    fn io(&self) -> (usize, ActionSpace) {
+        (self.params.inputs(), ActionSpace::directions())
+    }
+    fn reset(&mut self, mut rng: RcRng) -> (GameState, f32, bool) {
+        self.state = State::new(&self.params, &self.layout, self.curriculum.level(), &mut rng);
+        self.rng = XorShiftRng::from_seed(rng.seed());
+        (self.state.build_state(), 0., false)
+    }
+
+    fn truncated(&self) -> bool {
+        self.state.truncated
+    }
+    fn rendering_info(&self) -> RenderingInfo {
+        let agent_layer = RenderingLayer {
+            name: "agent".into(),
+            points: vec![
+                Point {
+                    x: self.state.agent.x,
+                    y: self.state.agent.y,
+                },
+            ],
+        };
+        let block_layer = RenderingLayer {
+            name: "block".into(),
+            points: self.state
+                .blocks
+                .iter()
+                .map(|s| Point { x: s.x, y: s.y })
+                .collect(),
+        };
+        let food_layer = RenderingLayer {
+            name: "food".into(),
+            points: self.state
+                .food
+                .iter()
+                .map(|s| Point { x: s.x, y: s.y })
+                .collect(),
+        };
+
+        let hazard_layer = RenderingLayer {
+            name: "hazard".into(),
+            points: self.state
+                .hazards
+                .iter()
+                .map(|h| Point { x: h.at.x, y: h.at.y })
+                .collect(),
+        };
+
+        RenderingInfo {
+            width: self.state.width,
+            height: self.state.height,
+            layers: vec![food_layer, agent_layer, block_layer, hazard_layer],
+        }
+    }
+
+    fn step(&mut self, sys: &SpatiumSys, action: &Action) -> (GameState, f32, bool) {
+        let direction: Direction = action.into();
+        let result = self.state.step(sys, &direction, &mut self.rng);
+
+        if result.2 {
+            self.curriculum.episode_done(self.state.eaten > 0);
+        }
+
+        result
+    }
+
+    fn level(&self) -> Option<usize> {
+        Some(self.curriculum.level())
+    }
+
+    fn set_level(&mut self, level: usize) {
+        self.curriculum.set_level(level);
+    }
+
+    fn eval(&self, sys: &SpatiumSys, model: &Box<Network + Send>) {
+        // starting boards laid out the way episodes are, so the observations
+        // have the shape the model was built for
+        let mut rng = RcRng::from_seed([1, 2, 3, 4]);
+        let level = self.curriculum.level();
+
+        let result: Vec<_> = (0..3)
+            .map(|_| {
+                let state = State::new(&self.params, &self.layout, level, &mut rng);
+                let (action, value) = model.test(sys, &state.build_state());
+
+                // compare against the optimal policy for the same board
+                let layout = planner::Layout {
+                    agent: state.agent.clone(),
+                    food: state.food.clone(),
+                    blocks: state.blocks.clone(),
+                };
+                let plan = planner::solve(&self.params, &layout, 0.9);
+                let regret = plan.regret(&layout.agent, &action);
+                (Direction::from(&action), value, regret)
+            })
+            .collect();
+        let regret = result.iter().fold(0., |a, r| a + r.2);
+        println!("{:?} regret={}", result, regret);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::weak_rng;
+    use HeadlessSys;
+
+    #[test]
+    fn test_parse_map() {
+        let map = parse_map("A.#\n.#.\n..F\n", 3).unwrap();
+        assert_eq!(map.agent, Some(sprite(0, 0)));
+        assert_eq!(map.food, vec![sprite(2, 2)]);
+        assert_eq!(map.blocks, vec![sprite(2, 0), sprite(1, 1)]);
+    }
+
+    #[test]
+    fn test_parse_map_errors() {
+        assert!(parse_map("A..\n...\n", 3).is_err());
+        assert!(parse_map("A..\n....\n..F\n", 3).is_err());
+        assert!(parse_map("A..\n.x.\n..F\n", 3).is_err());
+        assert!(parse_map("A..\n.A.\n..F\n", 3).is_err());
+        assert!(parse_map("A##\n###\n##F\n", 3).is_ok());
+        assert!(parse_map("###\n###\n##F\n", 3).is_err());
+    }
+
+    #[test]
+    fn test_map_layout() {
+        let p = Game1Parameters {
+            size: 4,
+            map: Some("....\n.##.\n.#F.\nA...".into()),
+            ..Default::default()
+        };
+        let mut rng = RcRng::new(Box::new(weak_rng()));
+        let state = State::new(&p, &p.layout().unwrap(), 1, &mut rng);
+        assert_eq!(state.agent, sprite(0, 3));
+        assert_eq!(state.food, vec![sprite(2, 2)]);
+        assert_eq!(state.blocks.len(), 3);
+    }
+
+    #[test]
+    fn test_food_reachable() {
+        let p = Game1Parameters {
+            size: 3,
+            map: Some("A#F\n##.\n...".into()),
+            ..Default::default()
+        };
+        assert!(p.validate().is_err());
+        // random blocks can only make things worse
+        let random_blocks = Game1Parameters {
+            random_blocks: 1,
+            ..p.clone()
+        };
+        assert!(random_blocks.validate().is_err());
+        // a random agent could start in the bottom right corner
+        let pocket = Game1Parameters {
+            map: Some("F..\n.##\n.#.".into()),
+            ..p.clone()
+        };
+        assert!(pocket.validate().is_err());
+
+        let mut state = State::empty(&p);
+        state.blocks = p.layout().unwrap().blocks;
+        state.agent = sprite(0, 0);
+        state.food = vec![sprite(2, 2)];
+        assert!(!state.food_reachable());
+        state.blocks.remove(0);
+        assert!(state.food_reachable());
+    }
+
+    #[test]
+    fn test_layout_fallback() {
+        // the only free square is on the way to the food, so every random
+        // block cuts it off and the layout goes without
+        let p = Game1Parameters {
+            map: Some("A.F\n###\n###".into()),
+            random_blocks: 1,
+            ..Default::default()
+        };
+        p.validate().unwrap();
+        let mut rng = RcRng::new(Box::new(weak_rng()));
+        let state = State::new(&p, &p.layout().unwrap(), 0, &mut rng);
+        assert_eq!(state.blocks.len(), 6);
+        assert!(state.food_reachable());
+
+        // all three food items on the map count towards the room needed
+        let crowded = Game1Parameters {
+            map: Some("AF.\nFF.\n...".into()),
+            random_blocks: 6,
+            ..Default::default()
+        };
+        assert!(crowded.validate().is_err());
+    }
+
+    #[test]
+    fn test_eat_all() {
+        let p = Game1Parameters {
+            size: 4,
+            random_blocks: 3,
+            food_count: 3,
+            food_reward: 5.,
+            eat_all: true,
+            ..Default::default()
+        };
+        p.validate().unwrap();
+        let mut rng = RcRng::new(Box::new(weak_rng()));
+        let mut state = State::new(&p, &p.layout().unwrap(), 4, &mut rng);
+        assert_eq!(state.blocks.len(), 4);
+        assert_eq!(state.food.len(), 3);
+        assert!(state.food_reachable());
+
+        // eating one item keeps the episode going
+        state.food = vec![sprite(1, 0), sprite(3, 3)];
+        state.blocks = vec![];
+        state.agent = sprite(0, 0);
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let (_, reward, done) = state.step(&HeadlessSys, &Direction::Right, &mut rng);
+        assert_eq!((reward, done), (5., false));
+        assert_eq!(state.food, vec![sprite(3, 3)]);
+    }
+
+    fn hazard_state(policy: HazardPolicy, at: Sprite, heading: Direction) -> State {
+        let p = Game1Parameters {
+            size: 5,
+            hazards: 1,
+            hazard_policy: policy,
+            hazard_penalty: 3.,
+            ..Default::default()
+        };
+        let mut state = State::empty(&p);
+        state.food = vec![sprite(4, 0)];
+        state.hazards = vec![Hazard { at: at, heading: heading }];
+        state
+    }
+
+    #[test]
+    fn test_hazard_patrol() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let mut state = hazard_state(HazardPolicy::Patrol, sprite(3, 4), Direction::Right);
+
+        // turns around at the edge of the board
+        state.step(&HeadlessSys, &Direction::Down, &mut rng);
+        assert_eq!(state.hazards[0].at, sprite(4, 4));
+        state.step(&HeadlessSys, &Direction::Down, &mut rng);
+        assert_eq!(state.hazards[0].at, sprite(3, 4));
+        assert_eq!(state.hazards[0].heading, Direction::Left);
+        assert_eq!(state.build_state().arr.shape(), &[3, 5, 5]);
+    }
+
+    #[test]
+    fn test_hazard_chase() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let mut state = hazard_state(HazardPolicy::Chase, sprite(3, 3), Direction::Up);
+
+        let (_, reward, done) = state.step(&HeadlessSys, &Direction::Down, &mut rng);
+        assert_eq!((reward, done), (0., false));
+        assert_eq!(distance(&state.hazards[0].at, &state.agent), 4);
+
+        // walking into a hazard costs the penalty
+        state.hazards[0].at = sprite(0, 2);
+        let (_, reward, done) = state.step(&HeadlessSys, &Direction::Down, &mut rng);
+        assert_eq!((reward, done), (-3., true));
+    }
+
+    #[test]
+    fn test_penalties() {
+        let p = Game1Parameters {
+            step_penalty: 0.1,
+            wall_penalty: 1.,
+            ..Default::default()
+        };
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let mut state = State::empty(&p);
+        state.food = vec![sprite(1, 0)];
+
+        // bumping into the edge of the board
+        let (_, reward, _) = state.step(&HeadlessSys, &Direction::Up, &mut rng);
+        assert!((reward + 1.1).abs() < 1e-6);
+        let (_, reward, done) = state.step(&HeadlessSys, &Direction::Right, &mut rng);
+        assert!((reward - 9.9).abs() < 1e-6);
+        assert!(done);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let p = Game1Parameters {
+            max_steps: 2,
+            ..Default::default()
+        };
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+
+        // running out of steps
+        let mut state = State::empty(&p);
+        state.food = vec![sprite(2, 2)];
+        state.step(&HeadlessSys, &Direction::Right, &mut rng);
+        let (_, _, done) = state.step(&HeadlessSys, &Direction::Right, &mut rng);
+        assert!(done && state.truncated);
+
+        // reaching the food on the last step is a real ending
+        let mut state = State::empty(&p);
+        state.food = vec![sprite(2, 0)];
+        state.step(&HeadlessSys, &Direction::Right, &mut rng);
+        let (_, _, done) = state.step(&HeadlessSys, &Direction::Right, &mut rng);
+        assert!(done && !state.truncated);
+    }
+
+    #[test]
+    fn test_slip() {
+        let p = Game1Parameters {
+            size: 5,
+            slip: 1.,
+            ..Default::default()
+        };
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let mut state = State::empty(&p);
+        state.agent = sprite(2, 2);
+        state.food = vec![sprite(4, 4)];
+
+        // always slips sideways
+        state.step(&HeadlessSys, &Direction::Up, &mut rng);
+        assert_eq!(state.agent.y, 2);
+        assert!(state.agent.x == 1 || state.agent.x == 3);
+
+        let nan = Game1Parameters {
+            slip: ::std::f32::NAN,
+            ..Default::default()
+        };
+        assert!(nan.validate().is_err());
+    }
+
+    #[test]
+    fn test_local_view() {
+        let p = Game1Parameters {
+            size: 4,
+            observation: Observation::Local { size: 3 },
+            ..Default::default()
+        };
+        let mut state = State::empty(&p);
+        state.blocks = vec![sprite(1, 1)];
+        state.agent = sprite(0, 0);
+        state.food = vec![sprite(1, 0)];
+
+        let arr = state.build_state().arr;
+        assert_eq!(arr.shape(), &[2, 3, 3]);
+        assert_eq!(arr.len(), p.inputs());
+        // off the board above and to the left, plus the block at (1, 1)
+        let walls: Vec<_> = (0..9).map(|i| arr[[0, i / 3, i % 3]]).collect();
+        assert_eq!(walls, vec![1, 1, 1, 1, 0, 0, 1, 0, 1]);
+        assert_eq!(arr[[1, 1, 2]], 1);
+
+        let invalid = Game1Parameters {
+            observation: Observation::Local { size: 4 },
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_feature_observations() {
+        let mut p = Game1Parameters {
+            size: 5,
+            observation: Observation::Coordinates,
+            ..Default::default()
+        };
+        let mut state = State::empty(&p);
+        state.blocks = vec![sprite(1, 1)];
+        state.agent = sprite(1, 0);
+        state.food = vec![sprite(3, 4), sprite(1, 2)];
+
+        let coordinates = state.build_state();
+        assert_eq!(coordinates.features, vec![0.25, 0., 0., 0.5]);
+        assert_eq!(coordinates.features.len(), p.inputs());
+
+        p.observation = Observation::Rays;
+        state.observation = Observation::Rays;
+        let rays = state.build_state().features;
+        assert_eq!(rays.len(), p.inputs());
+        // blocked by (1, 1) looking down, with no food in sight
+        assert_eq!(&rays[4..6], &[0., 0.]);
+        // three squares to the right edge
+        assert_eq!(&rays[2..4], &[0.6, 0.]);
+
+        state.blocks = vec![];
+        let rays = state.build_state().features;
+        // the food two squares down
+        assert_eq!(&rays[4..6], &[0.8, 0.8]);
+    }
+
+    #[test]
+    fn test_invalid_map_parameters() {
+        let p = r#"{"type": "Game1", "maxSteps": 10, "size": 3, "map": "A.\n.F"}"#;
+        assert!(p.into_parameters().is_err());
+
+        // without `random` the agent and food left off the map go in the
+        // corners, which have to be free
+        let fixed = |map: &str| Game1Parameters {
+            random: false,
+            map: Some(map.into()),
+            ..Default::default()
+        };
+        assert!(fixed("#..\n...\n..F").validate().is_err());
+        assert!(fixed("A..\n...\n..#").validate().is_err());
+        assert!(fixed("F..\n...\n...").validate().is_err());
+        assert!(fixed(".#.\n...\n...").validate().is_ok());
+        let corners = fixed(".#.\n...\n...").layout().unwrap();
+        assert_eq!(corners.agent, Some(sprite(0, 0)));
+        assert_eq!(corners.food, vec![sprite(2, 2)]);
+    }
+}
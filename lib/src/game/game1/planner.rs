@@ -0,0 +1,305 @@
+use super::{sprite, Game1Parameters, Sprite, State};
+use action::{Action, Direction};
+use game::GameState;
+
+const MAX_ITERATIONS: usize = 1000;
+const TOLERANCE: f32 = 1e-6;
+
+/// A concrete Game1 board: where the agent starts, the food and the blocks.
+#[derive(Clone, Debug)]
+pub struct Layout {
+    pub agent: Sprite,
+    pub food: Vec<Sprite>,
+    pub blocks: Vec<Sprite>,
+}
+
+impl Layout {
+    /// Recover the layout from a full board observation built by
+    /// `State::build_state`.
+    pub fn from_state(p: &Game1Parameters, s: &GameState) -> Result<Layout, String> {
+        let mut agent = sprite(0, 0);
+        let mut food = vec![];
+        let mut blocks = vec![];
+        let blocks_layer = if p.show_food() { 2 } else { 1 };
+
+        for y in 0..p.size {
+            for x in 0..p.size {
+                if s.arr[[0, y, x]] == 1 {
+                    agent = sprite(x, y);
+                }
+                if p.show_food() && s.arr[[1, y, x]] == 1 {
+                    food.push(sprite(x, y));
+                }
+                if p.show_blocks() && s.arr[[blocks_layer, y, x]] == 1 {
+                    blocks.push(sprite(x, y));
+                }
+            }
+        }
+
+        // layers are left out of the observation when they never change
+        let layout = p.layout()?;
+        if !p.show_food() {
+            food = layout.food;
+        }
+        if !p.show_blocks() {
+            blocks = layout.blocks;
+        }
+
+        Ok(Layout {
+            agent: agent,
+            food: food,
+            blocks: blocks,
+        })
+    }
+}
+
+/// Optimal state values and action values for every agent position on a
+/// fixed layout, with all of its food still on the board.
+pub struct Plan {
+    width: usize,
+    values: Vec<f32>,
+    q_values: Vec<Vec<f32>>,
+    actionable: Vec<bool>,
+}
+
+impl Plan {
+    fn index(&self, at: &Sprite) -> usize {
+        at.y * self.width + at.x
+    }
+    pub fn value(&self, at: &Sprite) -> f32 {
+        self.values[self.index(at)]
+    }
+    pub fn q_value(&self, at: &Sprite, action: &Action) -> f32 {
+        let action_i: usize = action.into();
+        self.q_values[self.index(at)][action_i]
+    }
+    /// The optimal action, or `None` when the agent cannot act from this
+    /// position (it is on food or inside a block).
+    pub fn action(&self, at: &Sprite) -> Option<Action> {
+        let i = self.index(at);
+        if !self.actionable[i] {
+            return None;
+        }
+        let (action_i, _) = self.q_values[i].iter().enumerate().fold(
+            (0, ::std::f32::MIN),
+            |(max_i, max_q), (i, q)| if *q > max_q { (i, *q) } else { (max_i, max_q) },
+        );
+        Some(action_i.into())
+    }
+    /// How much value is lost by taking `action` instead of the optimal one.
+    pub fn regret(&self, at: &Sprite, action: &Action) -> f32 {
+        self.value(at) - self.q_value(at, action)
+    }
+}
+
+/// Solve a layout by value iteration. The step limit is ignored, so values
+/// are for the infinite horizon discounted problem. Hazards move, so they
+/// are left out of the layout and ignored.
+///
+/// With `eat_all` the state includes which food is left, so the work grows
+/// with `2 ^ food_count`.
+pub fn solve(p: &Game1Parameters, layout: &Layout, discount: f32) -> Plan {
+    let mut state = State::empty(p);
+    state.blocks = layout.blocks.clone();
+    state.food = layout.food.clone();
+
+    let width = state.width;
+    let cells = state.width * state.height;
+    let actions = Direction::all();
+
+    // a bitmask of the food left, where an empty mask ends the episode.
+    // Without `eat_all` any food ends it, so one bit is enough.
+    let full = if state.eat_all {
+        (1 << state.food.len()) - 1
+    } else {
+        1
+    };
+    let states = (full + 1) * cells;
+    let left = |mask: usize, food: usize| !state.eat_all || mask & (1 << food) != 0;
+
+    // the agent never stands on food that is left or inside a block
+    let mut actionable = vec![true; states];
+    for mask in 0..full + 1 {
+        for i in 0..cells {
+            let at = sprite(i % width, i / width);
+            let on_food = state
+                .food
+                .iter()
+                .enumerate()
+                .any(|(f, s)| left(mask, f) && s.touches(&at));
+            let on_block = state.blocks.iter().any(|s| s.touches(&at));
+            actionable[mask * cells + i] = mask != 0 && !on_food && !on_block;
+        }
+    }
+
+    // (next state, reward) for a move in each direction from every state
+    let mut moves = vec![vec![]; states];
+    for i in 0..states {
+        if !actionable[i] {
+            continue;
+        }
+        let (mask, cell) = (i / cells, i % cells);
+        let at = sprite(cell % width, cell / width);
+        for action in &actions {
+            let target = state.move_from(&at, action);
+            let eaten = state
+                .food
+                .iter()
+                .enumerate()
+                .position(|(f, s)| left(mask, f) && s.touches(&target));
+            let (next_mask, mut reward) = match eaten {
+                Some(f) if state.eat_all => (mask & !(1 << f), state.food_reward),
+                Some(_) => (0, state.food_reward),
+                None => (mask, 0.),
+            };
+            reward -= state.step_penalty;
+            if target == at {
+                reward -= state.wall_penalty;
+            }
+            moves[i].push((next_mask * cells + target.y * width + target.x, reward));
+        }
+    }
+
+    // (probability, direction) of each move an action can end up making
+    let outcomes: Vec<Vec<(f32, usize)>> = actions
+        .iter()
+        .map(|action| {
+            let mut outcomes = vec![(1. - state.slip, action_index(action))];
+            for side in &action.perpendicular() {
+                outcomes.push((state.slip / 2., action_index(side)));
+            }
+            outcomes
+        })
+        .collect();
+
+    let mut values = vec![0f32; states];
+    let mut q_values = vec![vec![0f32; actions.len()]; states];
+
+    for _ in 0..MAX_ITERATIONS {
+        let mut delta = 0f32;
+        for i in 0..states {
+            if !actionable[i] {
+                continue;
+            }
+            let mut best = ::std::f32::MIN;
+            for (a, outcomes) in outcomes.iter().enumerate() {
+                let q = outcomes.iter().fold(0., |q, &(p, d)| {
+                    let (next, reward) = moves[i][d];
+                    q + p * (reward + discount * values[next])
+                });
+                q_values[i][a] = q;
+                best = best.max(q);
+            }
+            delta = delta.max((best - values[i]).abs());
+            values[i] = best;
+        }
+        if delta < TOLERANCE {
+            break;
+        }
+    }
+
+    let start = full * cells;
+    Plan {
+        width: width,
+        values: values.split_off(start),
+        q_values: q_values.split_off(start),
+        actionable: actionable.split_off(start),
+    }
+}
+
+fn action_index(d: &Direction) -> usize {
+    let action: Action = (*d).into();
+    action.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_solve() {
+        let p = Game1Parameters {
+            random: false,
+            ..Default::default()
+        };
+        let layout = Layout {
+            agent: sprite(0, 0),
+            food: vec![sprite(2, 2)],
+            blocks: vec![sprite(1, 1)],
+        };
+        let plan = solve(&p, &layout, 0.9);
+
+        // four moves around the block, the last one eats the food
+        let expected = 10. * 0.9 * 0.9 * 0.9;
+        assert!((plan.value(&sprite(0, 0)) - expected).abs() < 1e-4);
+
+        assert_eq!(plan.action(&sprite(2, 1)), Some(Action::from(Direction::Down)));
+        assert_eq!(plan.action(&sprite(1, 2)), Some(Action::from(Direction::Right)));
+        assert_eq!(plan.action(&sprite(2, 2)), None);
+        assert_eq!(plan.action(&sprite(1, 1)), None);
+
+        assert_eq!(plan.regret(&sprite(2, 1), &Direction::Down.into()), 0.);
+        assert!(plan.regret(&sprite(2, 1), &Direction::Up.into()) > 0.);
+    }
+
+    #[test]
+    fn test_solve_slip() {
+        let p = Game1Parameters {
+            slip: 0.2,
+            ..Default::default()
+        };
+        let layout = Layout {
+            agent: sprite(0, 0),
+            food: vec![sprite(2, 2)],
+            blocks: vec![sprite(1, 1)],
+        };
+        let plan = solve(&p, &layout, 0.9);
+
+        // next to the food, slipping either way is blocked so the agent
+        // stays put
+        let at = sprite(2, 1);
+        let expected = 0.8 * 10. + 0.2 * 0.9 * plan.value(&at);
+        assert!((plan.value(&at) - expected).abs() < 1e-3);
+        assert!(plan.value(&sprite(0, 0)) < 10. * 0.9 * 0.9 * 0.9);
+    }
+
+    #[test]
+    fn test_solve_eat_all() {
+        let p = Game1Parameters {
+            eat_all: true,
+            ..Default::default()
+        };
+        let layout = Layout {
+            agent: sprite(0, 0),
+            food: vec![sprite(2, 0), sprite(0, 2)],
+            blocks: vec![sprite(1, 1)],
+        };
+        let plan = solve(&p, &layout, 0.9);
+
+        // two moves to the first food then four more to the second
+        let expected = 10. * 0.9 + 10. * 0.9_f32.powi(5);
+        assert!((plan.value(&sprite(0, 0)) - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_from_state() {
+        let p = Game1Parameters {
+            random: false,
+            ..Default::default()
+        };
+        let mut state = State::empty(&p);
+        state.agent = sprite(1, 0);
+
+        // the fixed food and blocks come from the parameters
+        let layout = Layout::from_state(&p, &state.build_state()).unwrap();
+        assert_eq!(layout.agent, sprite(1, 0));
+        assert_eq!(layout.food, vec![sprite(2, 2)]);
+        assert_eq!(layout.blocks, vec![sprite(1, 1)]);
+
+        let bad_map = Game1Parameters {
+            map: Some("A..\n.x.\n..F\n".into()),
+            ..p
+        };
+        assert!(Layout::from_state(&bad_map, &state.build_state()).is_err());
+    }
+}
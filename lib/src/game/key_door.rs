@@ -0,0 +1,318 @@
+use super::*;
+use action::*;
+use SpatiumSys;
+
+use rand::distributions::{IndependentSample, Range};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct KeyDoorParameters {
+    pub max_steps: usize,
+    /// The board is split down the middle by a wall with a locked door in
+    /// it. The agent and key start on the left and the food is on the right.
+    pub size: usize,
+    pub food_reward: f32,
+    /// Rewards for picking up the key and opening the door, which shorten
+    /// the chain of steps to the food. Both are 0 by default so only the
+    /// food is rewarded.
+    pub key_reward: f32,
+    pub door_reward: f32,
+}
+
+impl Default for KeyDoorParameters {
+    fn default() -> Self {
+        KeyDoorParameters {
+            max_steps: 100,
+            size: 7,
+            food_reward: 10.,
+            key_reward: 0.,
+            door_reward: 0.,
+        }
+    }
+}
+
+impl KeyDoorParameters {
+    pub fn validate(&self) -> Result<(), String> {
+        // the left side needs room for the agent and the key
+        if self.size < 3 {
+            return Err("The key and door board must be at least 3 wide".into());
+        }
+        Ok(())
+    }
+}
+
+pub struct KeyDoor {
+    params: KeyDoorParameters,
+    state: State,
+}
+
+struct State {
+    max_steps: usize,
+    size: usize,
+    food_reward: f32,
+    key_reward: f32,
+    door_reward: f32,
+    step: usize,
+    agent: (usize, usize),
+    key: (usize, usize),
+    door: (usize, usize),
+    food: (usize, usize),
+    has_key: bool,
+    door_open: bool,
+    done: bool,
+    truncated: bool,
+}
+
+impl State {
+    fn new(p: &KeyDoorParameters, rng: &mut RcRng) -> Self {
+        let wall = p.size / 2;
+        let rows = Range::new(0, p.size);
+        let left = Range::new(0, wall);
+        let right = Range::new(wall + 1, p.size);
+
+        let agent = (left.ind_sample(rng), rows.ind_sample(rng));
+        let mut key = agent;
+        while key == agent {
+            key = (left.ind_sample(rng), rows.ind_sample(rng));
+        }
+
+        State {
+            max_steps: p.max_steps,
+            size: p.size,
+            food_reward: p.food_reward,
+            key_reward: p.key_reward,
+            door_reward: p.door_reward,
+            step: 0,
+            agent: agent,
+            key: key,
+            door: (wall, rows.ind_sample(rng)),
+            food: (right.ind_sample(rng), rows.ind_sample(rng)),
+            has_key: false,
+            door_open: false,
+            done: false,
+            truncated: false,
+        }
+    }
+    fn is_wall(&self, x: usize, y: usize) -> bool {
+        x == self.size / 2 && (x, y) != self.door
+    }
+    fn inputs(size: usize) -> usize {
+        // five layers plus the held key flag
+        size * size * 5 + 1
+    }
+    fn build_state(&self) -> GameState {
+        let mut state: ArrayD<u8> = Array::zeros(IxDyn(&[5, self.size, self.size]));
+
+        state[[0, self.agent.1, self.agent.0]] = 1;
+        for y in 0..self.size {
+            if self.is_wall(self.size / 2, y) {
+                state[[1, y, self.size / 2]] = 1;
+            }
+        }
+        if !self.has_key {
+            state[[2, self.key.1, self.key.0]] = 1;
+        }
+        if !self.door_open {
+            state[[3, self.door.1, self.door.0]] = 1;
+        }
+        state[[4, self.food.1, self.food.0]] = 1;
+
+        let has_key = if self.has_key { 1. } else { 0. };
+        GameState {
+            arr: state,
+            features: vec![has_key],
+        }
+    }
+    fn update_state(&mut self, reward: f32) -> (GameState, f32, bool) {
+        self.step += 1;
+        if self.step >= self.max_steps && !self.done {
+            self.done = true;
+            self.truncated = true;
+        }
+        (self.build_state(), reward, self.done)
+    }
+    fn step(&mut self, action: &Direction) -> (GameState, f32, bool) {
+        if self.done {
+            panic!("Game already done");
+        }
+
+        let (x, y) = self.agent;
+        let target = match *action {
+            Direction::Up if y > 0 => (x, y - 1),
+            Direction::Right if x < self.size - 1 => (x + 1, y),
+            Direction::Down if y < self.size - 1 => (x, y + 1),
+            Direction::Left if x > 0 => (x - 1, y),
+            _ => self.agent,
+        };
+
+        let mut reward = 0.;
+        if target == self.door && !self.door_open {
+            // the key opens the door, which then stays open
+            if !self.has_key {
+                return self.update_state(reward);
+            }
+            self.door_open = true;
+            reward += self.door_reward;
+        }
+        if self.is_wall(target.0, target.1) {
+            return self.update_state(reward);
+        }
+        self.agent = target;
+
+        if !self.has_key && self.agent == self.key {
+            self.has_key = true;
+            reward += self.key_reward;
+        }
+        if self.agent == self.food {
+            reward += self.food_reward;
+            self.done = true;
+        }
+
+        self.update_state(reward)
+    }
+}
+
+impl KeyDoor {
+    pub fn new(p: KeyDoorParameters, mut rng: RcRng) -> Box<Game + Send> {
+        let state = State::new(&p, &mut rng);
+        let game = KeyDoor {
+            params: p,
+            state: state,
+        };
+        Box::new(game)
+    }
+}
+
+impl Game for KeyDoor {
+    fn io(&self) -> (usize, ActionSpace) {
+        (State::inputs(self.params.size), ActionSpace::directions())
+    }
+    fn reset(&mut self, mut rng: RcRng) -> (GameState, f32, bool) {
+        self.state = State::new(&self.params, &mut rng);
+        (self.state.build_state(), 0., false)
+    }
+    fn step(&mut self, _sys: &SpatiumSys, action: &Action) -> (GameState, f32, bool) {
+        let direction: Direction = action.into();
+        self.state.step(&direction)
+    }
+    fn truncated(&self) -> bool {
+        self.state.truncated
+    }
+    fn rendering_info(&self) -> RenderingInfo {
+        let point = |(x, y): (usize, usize)| Point { x: x, y: y };
+        let size = self.state.size;
+
+        let wall_layer = RenderingLayer {
+            name: "wall".into(),
+            points: (0..size)
+                .filter(|y| self.state.is_wall(size / 2, *y))
+                .map(|y| point((size / 2, y)))
+                .collect(),
+        };
+        let mut key_layer = RenderingLayer {
+            name: "key".into(),
+            points: vec![],
+        };
+        if !self.state.has_key {
+            key_layer.points.push(point(self.state.key));
+        }
+        let mut door_layer = RenderingLayer {
+            name: "door".into(),
+            points: vec![],
+        };
+        if !self.state.door_open {
+            door_layer.points.push(point(self.state.door));
+        }
+        let food_layer = RenderingLayer {
+            name: "food".into(),
+            points: vec![point(self.state.food)],
+        };
+        let agent_layer = RenderingLayer {
+            name: "agent".into(),
+            points: vec![point(self.state.agent)],
+        };
+
+        RenderingInfo {
+            width: size,
+            height: size,
+            layers: vec![wall_layer, key_layer, door_layer, food_layer, agent_layer],
+        }
+    }
+    fn eval(&self, _sys: &SpatiumSys, _model: &Box<Network + Send>) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{SeedableRng, XorShiftRng};
+
+    fn state() -> State {
+        let p = KeyDoorParameters {
+            size: 5,
+            key_reward: 1.,
+            door_reward: 2.,
+            ..Default::default()
+        };
+        let mut rng = RcRng::new(Box::new(XorShiftRng::from_seed([1, 2, 3, 4])));
+        let mut state = State::new(&p, &mut rng);
+        state.agent = (1, 2);
+        state.key = (0, 0);
+        state.door = (2, 2);
+        state.food = (3, 2);
+        state
+    }
+
+    #[test]
+    fn test_locked_door() {
+        let mut state = state();
+
+        let (_, reward, done) = state.step(&Direction::Right);
+        assert_eq!(reward, 0.);
+        assert!(!done);
+        assert_eq!(state.agent, (1, 2));
+
+        // the wall is solid either side of the door
+        state.agent = (1, 1);
+        state.step(&Direction::Right);
+        assert_eq!(state.agent, (1, 1));
+    }
+
+    #[test]
+    fn test_key_opens_door() {
+        let mut state = state();
+        state.agent = (0, 1);
+
+        let (observation, reward, _) = state.step(&Direction::Up);
+        assert_eq!(reward, 1.);
+        assert!(state.has_key);
+        assert_eq!(observation.features, vec![1.]);
+        assert_eq!(observation.arr[[2, 0, 0]], 0);
+
+        state.agent = (1, 2);
+        let (observation, reward, _) = state.step(&Direction::Right);
+        assert_eq!(reward, 2.);
+        assert_eq!(state.agent, (2, 2));
+        assert_eq!(observation.arr[[3, 2, 2]], 0);
+
+        let (_, reward, done) = state.step(&Direction::Right);
+        assert_eq!(reward, 10.);
+        assert!(done);
+    }
+
+    #[test]
+    fn test_layout() {
+        let p = KeyDoorParameters::default();
+        let mut rng = RcRng::new(Box::new(XorShiftRng::from_seed([1, 2, 3, 4])));
+        for _ in 0..20 {
+            let state = State::new(&p, &mut rng);
+            assert!(state.agent.0 < p.size / 2);
+            assert!(state.key.0 < p.size / 2);
+            assert!(state.agent != state.key);
+            assert_eq!(state.door.0, p.size / 2);
+            assert!(state.food.0 > p.size / 2);
+
+            let x: Array2<f32> = (&state.build_state()).into();
+            assert_eq!(x.shape(), &[1, State::inputs(p.size)]);
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use super::*;
+use action::*;
+use SpatiumSys;
+
+use rand::Rng;
+
+const EXIT_REWARD: f32 = 10.;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MazeParameters {
+    pub max_steps: usize,
+    /// Number of cells along each side. Walls sit between the cells, so the
+    /// board is `2 * size + 1` squares wide.
+    pub size: usize,
+    /// Chance of knocking down each wall left standing after generating a
+    /// perfect maze, adding loops.
+    pub loopiness: f32,
+    /// Generate a new maze every episode rather than keeping the first one.
+    pub regenerate: bool,
+}
+
+impl Default for MazeParameters {
+    fn default() -> Self {
+        MazeParameters {
+            max_steps: 100,
+            size: 4,
+            loopiness: 0.,
+            regenerate: false,
+        }
+    }
+}
+
+impl MazeParameters {
+    pub fn validate(&self) -> Result<(), String> {
+        // a single cell would start the agent on the exit
+        if self.size < 2 {
+            return Err("The maze must be at least 2 cells wide".into());
+        }
+        Ok(())
+    }
+}
+
+/// Carve a maze with the recursive backtracker. Returns a `true` for every
+/// wall square of the `2 * size + 1` square board, row by row.
+fn generate(size: usize, loopiness: f32, rng: &mut RcRng) -> Vec<bool> {
+    let dim = 2 * size + 1;
+    let mut walls = vec![true; dim * dim];
+    let mut visited = vec![false; size * size];
+
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    walls[dim + 1] = false;
+
+    loop {
+        let (cx, cy) = match stack.last() {
+            Some(&cell) => cell,
+            None => break,
+        };
+
+        let mut neighbours = vec![];
+        if cx > 0 && !visited[cy * size + cx - 1] {
+            neighbours.push((cx - 1, cy));
+        }
+        if cx + 1 < size && !visited[cy * size + cx + 1] {
+            neighbours.push((cx + 1, cy));
+        }
+        if cy > 0 && !visited[(cy - 1) * size + cx] {
+            neighbours.push((cx, cy - 1));
+        }
+        if cy + 1 < size && !visited[(cy + 1) * size + cx] {
+            neighbours.push((cx, cy + 1));
+        }
+
+        match rng.choose(&neighbours).cloned() {
+            Some((nx, ny)) => {
+                visited[ny * size + nx] = true;
+                // open the next cell and the wall between the two
+                walls[(2 * ny + 1) * dim + 2 * nx + 1] = false;
+                walls[(cy + ny + 1) * dim + cx + nx + 1] = false;
+                stack.push((nx, ny));
+            }
+            None => {
+                stack.pop();
+            }
+        }
+    }
+
+    // walls between two cells have one odd and one even coordinate
+    for y in 1..dim - 1 {
+        for x in 1..dim - 1 {
+            let between = (x + y) % 2 == 1;
+            if between && walls[y * dim + x] && rng.next_f32() < loopiness {
+                walls[y * dim + x] = false;
+            }
+        }
+    }
+
+    walls
+}
+
+pub struct Maze {
+    params: MazeParameters,
+    walls: Vec<bool>,
+    state: State,
+}
+
+struct State {
+    max_steps: usize,
+    dim: usize,
+    regenerate: bool,
+    step: usize,
+    walls: Vec<bool>,
+    agent: (usize, usize),
+    exit: (usize, usize),
+    done: bool,
+    truncated: bool,
+}
+
+impl State {
+    fn new(p: &MazeParameters, walls: Vec<bool>) -> Self {
+        let dim = 2 * p.size + 1;
+        State {
+            max_steps: p.max_steps,
+            dim: dim,
+            regenerate: p.regenerate,
+            step: 0,
+            walls: walls,
+            // opposite corners
+            agent: (1, 1),
+            exit: (dim - 2, dim - 2),
+            done: false,
+            truncated: false,
+        }
+    }
+    fn is_wall(&self, x: usize, y: usize) -> bool {
+        self.walls[y * self.dim + x]
+    }
+    fn build_state(&self) -> GameState {
+        let mut layers = 2;
+        if self.regenerate {
+            layers += 1;
+        }
+        let mut state: ArrayD<u8> = Array::zeros(IxDyn(&[layers, self.dim, self.dim]));
+
+        state[[0, self.agent.1, self.agent.0]] = 1;
+        state[[1, self.exit.1, self.exit.0]] = 1;
+        if self.regenerate {
+            for y in 0..self.dim {
+                for x in 0..self.dim {
+                    if self.is_wall(x, y) {
+                        state[[2, y, x]] = 1;
+                    }
+                }
+            }
+        }
+
+        GameState::grid(state)
+    }
+    fn update_state(&mut self) -> (GameState, f32, bool) {
+        let mut reward = 0.;
+        if self.agent == self.exit {
+            reward = EXIT_REWARD;
+            self.done = true;
+        }
+
+        self.step += 1;
+        if self.step >= self.max_steps && !self.done {
+            self.done = true;
+            self.truncated = true;
+        }
+
+        (self.build_state(), reward, self.done)
+    }
+    fn step(&mut self, action: &Direction) -> (GameState, f32, bool) {
+        if self.done {
+            panic!("Game already done");
+        }
+
+        // the border is all wall so there is no need to check the edges
+        let (x, y) = self.agent;
+        let target = match *action {
+            Direction::Up => (x, y - 1),
+            Direction::Right => (x + 1, y),
+            Direction::Down => (x, y + 1),
+            Direction::Left => (x - 1, y),
+        };
+        if !self.is_wall(target.0, target.1) {
+            self.agent = target;
+        }
+
+        self.update_state()
+    }
+}
+
+impl Maze {
+    pub fn new(p: MazeParameters, mut rng: RcRng) -> Box<Game + Send> {
+        let walls = generate(p.size, p.loopiness, &mut rng);
+        let state = State::new(&p, walls.clone());
+        let game = Maze {
+            params: p,
+            walls: walls,
+            state: state,
+        };
+        Box::new(game)
+    }
+}
+
+impl Game for Maze {
+    fn io(&self) -> (usize, ActionSpace) {
+        let mut layers = 2;
+        if self.params.regenerate {
+            layers += 1;
+        }
+        (self.state.dim * self.state.dim * layers, ActionSpace::directions())
+    }
+    fn reset(&mut self, mut rng: RcRng) -> (GameState, f32, bool) {
+        if self.params.regenerate {
+            self.walls = generate(self.params.size, self.params.loopiness, &mut rng);
+        }
+        self.state = State::new(&self.params, self.walls.clone());
+        (self.state.build_state(), 0., false)
+    }
+    fn step(&mut self, _sys: &SpatiumSys, action: &Action) -> (GameState, f32, bool) {
+        let direction: Direction = action.into();
+        self.state.step(&direction)
+    }
+    fn truncated(&self) -> bool {
+        self.state.truncated
+    }
+    fn rendering_info(&self) -> RenderingInfo {
+        let dim = self.state.dim;
+
+        let wall_layer = RenderingLayer {
+            name: "wall".into(),
+            points: (0..dim * dim)
+                .filter(|i| self.state.walls[*i])
+                .map(|i| Point {
+                    x: i % dim,
+                    y: i / dim,
+                })
+                .collect(),
+        };
+        let exit_layer = RenderingLayer {
+            name: "exit".into(),
+            points: vec![
+                Point {
+                    x: self.state.exit.0,
+                    y: self.state.exit.1,
+                },
+            ],
+        };
+        let agent_layer = RenderingLayer {
+            name: "agent".into(),
+            points: vec![
+                Point {
+                    x: self.state.agent.0,
+                    y: self.state.agent.1,
+                },
+            ],
+        };
+
+        RenderingInfo {
+            width: dim,
+            height: dim,
+            layers: vec![wall_layer, exit_layer, agent_layer],
+        }
+    }
+    fn eval(&self, _sys: &SpatiumSys, _model: &Box<Network + Send>) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{SeedableRng, XorShiftRng};
+
+    fn rng() -> RcRng {
+        RcRng::new(Box::new(XorShiftRng::from_seed([1, 2, 3, 4])))
+    }
+
+    // open squares reachable from the top left cell
+    fn reachable(walls: &[bool], dim: usize) -> usize {
+        let mut seen = vec![false; dim * dim];
+        let mut queue = vec![dim + 1];
+        seen[dim + 1] = true;
+        let mut count = 0;
+        while let Some(i) = queue.pop() {
+            count += 1;
+            for n in &[i - 1, i + 1, i - dim, i + dim] {
+                if !walls[*n] && !seen[*n] {
+                    seen[*n] = true;
+                    queue.push(*n);
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn test_perfect_maze() {
+        let size = 6;
+        let dim = 2 * size + 1;
+        let walls = generate(size, 0., &mut rng());
+
+        // a spanning tree over the cells opens every cell and one wall fewer
+        // than there are cells
+        let open = walls.iter().filter(|w| !**w).count();
+        assert_eq!(open, size * size + size * size - 1);
+        assert_eq!(reachable(&walls, dim), open);
+
+        // the border is intact
+        for i in 0..dim {
+            assert!(walls[i]);
+            assert!(walls[(dim - 1) * dim + i]);
+            assert!(walls[i * dim]);
+            assert!(walls[i * dim + dim - 1]);
+        }
+    }
+
+    #[test]
+    fn test_loopiness() {
+        let size = 6;
+        let perfect = generate(size, 0., &mut rng());
+        let loopy = generate(size, 1., &mut rng());
+
+        let open = |walls: &[bool]| walls.iter().filter(|w| !**w).count();
+        assert!(open(&loopy[..]) > open(&perfect[..]));
+    }
+
+    #[test]
+    fn test_invalid_size() {
+        for size in 0..2 {
+            let p = MazeParameters {
+                size: size,
+                ..Default::default()
+            };
+            assert!(p.validate().is_err());
+        }
+    }
+}
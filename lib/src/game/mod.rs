@@ -3,60 +3,181 @@ use serde_json as json;
 use ndarray::prelude::*;
 use RcRng;
 
+mod arena;
+mod cart_pole;
+mod cliff_walk;
+mod curriculum;
 mod game1;
+mod key_door;
+mod maze;
+mod snake;
 
 use super::SpatiumSys;
 use Network;
-pub use self::game1::Game1Parameters;
-use action::Action;
+pub use self::arena::ArenaParameters;
+pub use self::cart_pole::CartPoleParameters;
+pub use self::cliff_walk::CliffWalkParameters;
+pub use self::curriculum::{Curriculum, CurriculumParameters, CurriculumStrategy};
+pub use self::game1::{Game1Parameters, HazardPolicy, Observation};
+pub use self::game1::planner;
+pub use self::key_door::KeyDoorParameters;
+pub use self::maze::MazeParameters;
+pub use self::snake::SnakeParameters;
+use action::{Action, ActionSpace};
 
+/// Games report the reward for the latest step only, which may be negative.
+/// The episode score is the sum of these rewards.
 pub trait Game {
-    fn io(&self) -> (usize, usize);
-    fn reset(&mut self, rng: RcRng) -> (GameState, usize, bool);
-    fn step(&mut self, sys: &SpatiumSys, action: &Action) -> (GameState, usize, bool);
+    /// Number of inputs in a flattened `GameState` and the actions the game
+    /// accepts.
+    fn io(&self) -> (usize, ActionSpace);
+    fn reset(&mut self, rng: RcRng) -> (GameState, f32, bool);
+    fn step(&mut self, sys: &SpatiumSys, action: &Action) -> (GameState, f32, bool);
     fn rendering_info(&self) -> RenderingInfo;
+    /// Whether the episode that just ended ran out of steps rather than
+    /// reaching a terminal state, so its last state still has a value.
+    fn truncated(&self) -> bool {
+        false
+    }
+    /// The curriculum level, for games that have one.
+    fn level(&self) -> Option<usize> {
+        None
+    }
+    /// Play the following episodes at `level`, for games that have a
+    /// curriculum.
+    fn set_level(&mut self, _level: usize) {}
+    /// Number of agents sharing the board, each played by its own network.
+    fn agents(&self) -> usize {
+        1
+    }
+    /// Whether every agent is rewarded for what all of them do.
+    fn shared_reward(&self) -> bool {
+        false
+    }
+    /// `reset` with an observation and reward for every agent.
+    fn reset_agents(&mut self, rng: RcRng) -> (Vec<GameState>, Vec<f32>, bool) {
+        let (state, reward, done) = self.reset(rng);
+        (vec![state], vec![reward], done)
+    }
+    /// `step` with one action per agent.
+    fn step_agents(
+        &mut self,
+        sys: &SpatiumSys,
+        actions: &[Action],
+    ) -> (Vec<GameState>, Vec<f32>, bool) {
+        let (state, reward, done) = self.step(sys, &actions[0]);
+        (vec![state], vec![reward], done)
+    }
     fn eval(&self, &SpatiumSys, &Box<Network + Send>);
 }
 
+/// An observation. Networks see the one hot board layers followed by any
+/// continuous features as a single row.
 #[derive(Clone, Debug)]
 pub struct GameState {
     pub arr: ArrayD<u8>,
+    pub features: Vec<f32>,
+}
+
+impl GameState {
+    pub fn grid(arr: ArrayD<u8>) -> Self {
+        GameState {
+            arr: arr,
+            features: vec![],
+        }
+    }
+    pub fn from_features(features: Vec<f32>) -> Self {
+        GameState {
+            arr: Array::zeros(IxDyn(&[0])),
+            features: features,
+        }
+    }
 }
 
 impl<'a> Into<Array2<f32>> for &'a GameState {
     fn into(self) -> Array2<f32> {
-        let x: Vec<_> = self.arr.iter().map(|n| *n as f32).collect();
+        let x: Vec<_> = self.arr
+            .iter()
+            .map(|n| *n as f32)
+            .chain(self.features.iter().cloned())
+            .collect();
         let l = x.len();
         Array::from_shape_vec([1, l], x).unwrap()
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RenderingInfo {
-    width: usize,
-    height: usize,
-    layers: Vec<RenderingLayer>,
+    pub width: usize,
+    pub height: usize,
+    pub layers: Vec<RenderingLayer>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RenderingLayer {
-    name: String,
-    points: Vec<Point>,
+    pub name: String,
+    pub points: Vec<Point>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Point {
-    x: usize,
-    y: usize,
+    pub x: usize,
+    pub y: usize,
+}
+
+pub fn game_descriptions() -> Games {
+    Games {
+        game1: GameDescription {
+            id: "Game1".into(),
+            name: "Game 1".into(),
+            default_parameters: Default::default(),
+        },
+        snake: GameDescription {
+            id: "Snake".into(),
+            name: "Snake".into(),
+            default_parameters: Default::default(),
+        },
+        maze: GameDescription {
+            id: "Maze".into(),
+            name: "Maze".into(),
+            default_parameters: Default::default(),
+        },
+        arena: GameDescription {
+            id: "Arena".into(),
+            name: "Arena".into(),
+            default_parameters: Default::default(),
+        },
+        key_door: GameDescription {
+            id: "KeyDoor".into(),
+            name: "Key and Door".into(),
+            default_parameters: Default::default(),
+        },
+        cart_pole: GameDescription {
+            id: "CartPole".into(),
+            name: "Cart Pole".into(),
+            default_parameters: Default::default(),
+        },
+        cliff_walk: GameDescription {
+            id: "CliffWalk".into(),
+            name: "Cliff Walk".into(),
+            default_parameters: Default::default(),
+        },
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Games {
-    pub game1: GameDescription<(usize)>,
+    pub game1: GameDescription<Game1Parameters>,
+    pub snake: GameDescription<SnakeParameters>,
+    pub maze: GameDescription<MazeParameters>,
+    pub arena: GameDescription<ArenaParameters>,
+    pub key_door: GameDescription<KeyDoorParameters>,
+    pub cart_pole: GameDescription<CartPoleParameters>,
+    pub cliff_walk: GameDescription<CliffWalkParameters>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -67,10 +188,16 @@ pub struct GameDescription<P: serde::Serialize> {
     pub default_parameters: P,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum GameParameters {
     Game1(Game1Parameters),
+    Snake(SnakeParameters),
+    Maze(MazeParameters),
+    Arena(ArenaParameters),
+    KeyDoor(KeyDoorParameters),
+    CartPole(CartPoleParameters),
+    CliffWalk(CliffWalkParameters),
 }
 
 pub trait IntoGameParameters {
@@ -79,20 +206,40 @@ pub trait IntoGameParameters {
 
 impl IntoGameParameters for GameParameters {
     fn into_parameters(self) -> Result<GameParameters, String> {
+        self.validate()?;
         Ok(self)
     }
 }
 
 impl<'a> IntoGameParameters for &'a str {
     fn into_parameters(self) -> Result<GameParameters, String> {
-        json::from_str(self).map_err(|e| format!("{}. String was:\n{}", e, self))
+        let p: GameParameters =
+            json::from_str(self).map_err(|e| format!("{}. String was:\n{}", e, self))?;
+        p.into_parameters()
     }
 }
 
 impl GameParameters {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            GameParameters::Game1(ref p) => p.validate(),
+            GameParameters::Snake(ref p) => p.validate(),
+            GameParameters::Maze(ref p) => p.validate(),
+            GameParameters::Arena(ref p) => p.validate(),
+            GameParameters::KeyDoor(ref p) => p.validate(),
+            GameParameters::CartPole(ref p) => p.validate(),
+            GameParameters::CliffWalk(ref p) => p.validate(),
+        }
+    }
     pub fn into_game(self, rng: RcRng) -> (Box<Game + Send>) {
         match self {
             GameParameters::Game1(p) => game1::Game1::new(p, rng),
+            GameParameters::Snake(p) => snake::Snake::new(p, rng),
+            GameParameters::Maze(p) => maze::Maze::new(p, rng),
+            GameParameters::Arena(p) => arena::Arena::new(p, rng),
+            GameParameters::KeyDoor(p) => key_door::KeyDoor::new(p, rng),
+            GameParameters::CartPole(p) => cart_pole::CartPole::new(p, rng),
+            GameParameters::CliffWalk(p) => cliff_walk::CliffWalk::new(p, rng),
         }
     }
 }
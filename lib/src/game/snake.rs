@@ -0,0 +1,299 @@
+use super::*;
+use action::*;
+use SpatiumSys;
+
+use std::collections::VecDeque;
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+const FOOD_REWARD: f32 = 10.;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnakeParameters {
+    pub max_steps: usize,
+    pub size: usize,
+    pub starting_length: usize,
+}
+
+impl Default for SnakeParameters {
+    fn default() -> Self {
+        SnakeParameters {
+            max_steps: 100,
+            size: 8,
+            starting_length: 3,
+        }
+    }
+}
+
+impl SnakeParameters {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.size < 2 {
+            return Err("The snake board must be at least 2 wide".into());
+        }
+        // the snake starts in the middle with its tail trailing left, so it
+        // must fit in the left half of its row
+        let max_length = self.size / 2 + 1;
+        if self.starting_length == 0 || self.starting_length > max_length {
+            return Err(format!(
+                "startingLength must be between 1 and {} on this board",
+                max_length
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub struct Snake {
+    params: SnakeParameters,
+    // food respawns mid episode, so the game keeps its own rng seeded on reset
+    rng: XorShiftRng,
+    state: State,
+}
+
+struct State {
+    max_steps: usize,
+    width: usize,
+    height: usize,
+    step: usize,
+    // head first
+    body: VecDeque<(usize, usize)>,
+    heading: Direction,
+    food: (usize, usize),
+    done: bool,
+    truncated: bool,
+}
+
+impl State {
+    fn new(p: &SnakeParameters, rng: &mut XorShiftRng) -> Self {
+        // start in the middle heading right with the tail trailing left
+        let (cx, cy) = (p.size / 2, p.size / 2);
+        let body = (0..p.starting_length).map(|i| (cx - i, cy)).collect();
+
+        let mut state = State {
+            max_steps: p.max_steps,
+            width: p.size,
+            height: p.size,
+            step: 0,
+            body: body,
+            heading: Direction::Right,
+            food: (0, 0),
+            done: false,
+            truncated: false,
+        };
+        state.food = state.random_empty_space(rng).expect("No room for food");
+        state
+    }
+    fn random_empty_space(&self, rng: &mut XorShiftRng) -> Option<(usize, usize)> {
+        let free: Vec<_> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|p| !self.body.contains(p))
+            .collect();
+        rng.choose(&free).cloned()
+    }
+    fn build_state(&self) -> GameState {
+        let mut state: ArrayD<u8> = Array::zeros(IxDyn(&[3, self.height, self.width]));
+
+        let (hx, hy) = self.body[0];
+        state[[0, hy, hx]] = 1;
+        for &(x, y) in self.body.iter().skip(1) {
+            state[[1, y, x]] = 1;
+        }
+        state[[2, self.food.1, self.food.0]] = 1;
+
+        GameState::grid(state)
+    }
+    fn update_state(&mut self, reward: f32) -> (GameState, f32, bool) {
+        self.step += 1;
+        if self.step >= self.max_steps && !self.done {
+            self.done = true;
+            self.truncated = true;
+        }
+        (self.build_state(), reward, self.done)
+    }
+    fn step(&mut self, action: &Direction, rng: &mut XorShiftRng) -> (GameState, f32, bool) {
+        if self.done {
+            panic!("Game already done");
+        }
+
+        // the snake can't turn back on itself
+        if self.body.len() == 1 || *action != self.heading.opposite() {
+            self.heading = *action;
+        }
+
+        let (x, y) = self.body[0];
+        let head = match self.heading {
+            Direction::Up if y > 0 => Some((x, y - 1)),
+            Direction::Right if x < self.width - 1 => Some((x + 1, y)),
+            Direction::Down if y < self.height - 1 => Some((x, y + 1)),
+            Direction::Left if x > 0 => Some((x - 1, y)),
+            _ => None,
+        };
+
+        // hit a wall
+        let head = match head {
+            Some(head) => head,
+            None => {
+                self.done = true;
+                return self.update_state(0.);
+            }
+        };
+
+        // the tail moves out of the way unless the snake is growing
+        let eating = head == self.food;
+        if !eating {
+            self.body.pop_back();
+        }
+
+        if self.body.contains(&head) {
+            self.done = true;
+            return self.update_state(0.);
+        }
+        self.body.push_front(head);
+
+        let mut reward = 0.;
+        if eating {
+            reward = FOOD_REWARD;
+            match self.random_empty_space(rng) {
+                Some(food) => self.food = food,
+                // filled the board
+                None => self.done = true,
+            }
+        }
+
+        self.update_state(reward)
+    }
+}
+
+impl Snake {
+    pub fn new(p: SnakeParameters, mut rng: RcRng) -> Box<Game + Send> {
+        let mut game_rng = XorShiftRng::from_seed(rng.seed());
+        let state = State::new(&p, &mut game_rng);
+        let game = Snake {
+            params: p,
+            rng: game_rng,
+            state: state,
+        };
+        Box::new(game)
+    }
+}
+
+impl Game for Snake {
+    fn io(&self) -> (usize, ActionSpace) {
+        let inputs = self.state.width * self.state.height * 3;
+        (inputs, ActionSpace::directions())
+    }
+    fn reset(&mut self, mut rng: RcRng) -> (GameState, f32, bool) {
+        self.rng = XorShiftRng::from_seed(rng.seed());
+        self.state = State::new(&self.params, &mut self.rng);
+        (self.state.build_state(), 0., false)
+    }
+    fn step(&mut self, _sys: &SpatiumSys, action: &Action) -> (GameState, f32, bool) {
+        let direction: Direction = action.into();
+        self.state.step(&direction, &mut self.rng)
+    }
+    fn truncated(&self) -> bool {
+        self.state.truncated
+    }
+    fn rendering_info(&self) -> RenderingInfo {
+        let (hx, hy) = self.state.body[0];
+        let head_layer = RenderingLayer {
+            name: "head".into(),
+            points: vec![Point { x: hx, y: hy }],
+        };
+        let body_layer = RenderingLayer {
+            name: "body".into(),
+            points: self.state
+                .body
+                .iter()
+                .skip(1)
+                .map(|&(x, y)| Point { x: x, y: y })
+                .collect(),
+        };
+        let food_layer = RenderingLayer {
+            name: "food".into(),
+            points: vec![
+                Point {
+                    x: self.state.food.0,
+                    y: self.state.food.1,
+                },
+            ],
+        };
+
+        RenderingInfo {
+            width: self.state.width,
+            height: self.state.height,
+            layers: vec![food_layer, body_layer, head_layer],
+        }
+    }
+    fn eval(&self, _sys: &SpatiumSys, _model: &Box<Network + Send>) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn state(starting_length: usize) -> (State, XorShiftRng) {
+        let p = SnakeParameters {
+            max_steps: 100,
+            size: 10,
+            starting_length: starting_length,
+        };
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let mut state = State::new(&p, &mut rng);
+        state.food = (0, 0);
+        (state, rng)
+    }
+
+    #[test]
+    fn test_grow() {
+        let (mut state, mut rng) = state(3);
+        state.food = (6, 5);
+
+        let (_, reward, done) = state.step(&Direction::Right, &mut rng);
+        assert_eq!(reward, FOOD_REWARD);
+        assert!(!done);
+        assert_eq!(state.body.len(), 4);
+        assert_eq!(state.body[0], (6, 5));
+        assert!(!state.body.contains(&state.food));
+    }
+
+    #[test]
+    fn test_no_reverse() {
+        let (mut state, mut rng) = state(3);
+
+        state.step(&Direction::Left, &mut rng);
+        assert_eq!(state.body[0], (6, 5));
+    }
+
+    #[test]
+    fn test_self_collision() {
+        let (mut state, mut rng) = state(5);
+
+        assert!(!state.step(&Direction::Up, &mut rng).2);
+        assert!(!state.step(&Direction::Left, &mut rng).2);
+        assert!(state.step(&Direction::Down, &mut rng).2);
+    }
+
+    #[test]
+    fn test_small_board() {
+        let mut p = SnakeParameters {
+            max_steps: 10,
+            size: 2,
+            starting_length: 2,
+        };
+        p.validate().unwrap();
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let state = State::new(&p, &mut rng);
+        assert_eq!(state.body.len(), 2);
+        assert!(!state.body.contains(&state.food));
+
+        p.starting_length = 3;
+        assert!(p.validate().is_err());
+        p.starting_length = 0;
+        assert!(p.validate().is_err());
+
+        p.starting_length = 1;
+        p.size = 1;
+        assert!(p.validate().is_err());
+    }
+}
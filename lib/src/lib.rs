@@ -8,27 +8,55 @@ extern crate serde;
 extern crate serde_derive;
 extern crate serde_json;
 
-#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
 extern crate rayon;
 
 mod action;
+mod env;
 mod game;
 mod network;
+mod raster;
+mod render;
+mod replay;
 mod rng;
 mod spatium;
 
 pub use spatium::Spatium;
 pub use rng::RcRng;
+pub use replay::Replay;
+pub use render::{Color, Glyph, TextRenderer};
+pub use raster::{encode_gif, encode_png_strip, Frame, Rasterizer};
+pub use env::{Env, Info, VecEnv};
+pub use action::{Action, ActionSpace, Direction};
 
 use game::*;
 use action::*;
 use network::*;
 
 pub use game::GameParameters;
+pub use game::{Game, GameState, IntoGameParameters};
+pub use game::{Point, RenderingInfo, RenderingLayer};
+pub use game::game_descriptions;
 pub use game::Game1Parameters;
+pub use game::HazardPolicy;
+pub use game::Observation;
+pub use game::{CurriculumParameters, CurriculumStrategy};
+pub use game::MazeParameters;
+pub use game::SnakeParameters;
+pub use game::ArenaParameters;
+pub use game::KeyDoorParameters;
+pub use game::CartPoleParameters;
+pub use game::CliffWalkParameters;
 
 pub use network::ModelParameters;
+pub use network::{IntoModelParameters, Network};
+pub use network::ActorCriticParameters;
+pub use network::EvolutionStrategies;
+pub use network::EvolutionStrategiesParameters;
+pub use network::GeneticParameters;
 pub use network::model_descriptions;
+pub use network::OracleParameters;
+pub use network::ReinforceParameters;
 pub use network::SingleLayerNetworkParameters;
 pub use network::DynamicValue;
 
@@ -38,11 +66,36 @@ use std::sync::{Arc, RwLock, RwLockReadGuard};
 #[serde(rename_all = "camelCase")]
 pub struct EpisodeResult {
     pub steps: usize,
+    /// Sum of the rewards over the episode, for all the agents together.
+    /// When they share their rewards every agent already scores everything
+    /// the team did, so this is one agent's score.
     pub score: f32,
+    /// Each agent's score, in games with more than one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scores: Option<Vec<f32>>,
 }
 
+/// What one agent did in a step of a game with more than one.
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+pub struct AgentStep {
+    pub action: String,
+    pub reward: f32,
+    pub metrics: Metrics,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_result: Option<GenerationResult>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationResult {
+    pub generation: usize,
+    pub best_fitness: f32,
+    pub mean_fitness: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Metrics {
     pub annotations: Vec<String>,
     pub values: Vec<(String, f32)>,
@@ -63,13 +116,27 @@ pub struct StepResult {
     pub global_step: usize,
     pub episode: usize,
     pub step: usize,
+    /// The first agent's action.
     pub action: String,
     pub done: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub agents: Option<Vec<AgentStep>>,
+    /// The curriculum level the step was played at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub episode_result: Option<EpisodeResult>,
+    /// The first agent's, like `metrics`. Games with more than one agent
+    /// report every agent's in `agents`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_result: Option<GenerationResult>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rendering_info: Option<RenderingInfo>,
+    /// The first agent's metrics.
     pub metrics: Option<Metrics>,
+    /// Set on the last step of an episode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replay: Option<Replay>,
 }
 
 impl StepResult {
@@ -86,19 +153,39 @@ impl StepResult {
             step: step,
             action: action,
             done: done,
+            agents: None,
+            level: None,
             episode_result: None,
+            generation_result: None,
             rendering_info: Some(rendering_info),
             metrics: None,
+            replay: None,
         }
     }
     fn with_metrics(mut self, metrics: Metrics) -> Self {
         self.metrics = Some(metrics);
         self
     }
+    fn with_generation_result(mut self, generation_result: Option<GenerationResult>) -> Self {
+        self.generation_result = generation_result;
+        self
+    }
+    fn with_agents(mut self, agents: Vec<AgentStep>) -> Self {
+        self.agents = Some(agents);
+        self
+    }
+    fn with_level(mut self, level: Option<usize>) -> Self {
+        self.level = level;
+        self
+    }
     fn with_episode_result(mut self, episode_result: EpisodeResult) -> Self {
         self.episode_result = Some(episode_result);
         self
     }
+    fn with_replay(mut self, replay: Replay) -> Self {
+        self.replay = Some(replay);
+        self
+    }
 }
 
 pub trait SpatiumSys {
@@ -139,6 +226,17 @@ impl <T: SpatiumSys> SpatiumSys for SpatiumSysHelper<T> {
     }
 }
 
+/// Sys for running games away from the UI, such as when evaluating a
+/// population of networks.
+pub struct HeadlessSys;
+
+impl SpatiumSys for HeadlessSys {
+    fn info(&self, _s: &str) {}
+    fn random(&mut self) -> f64 {
+        rand::random()
+    }
+}
+
 impl<T: SpatiumSys> SpatiumSysHelper<T> {
     fn new(t: T) -> SpatiumSysHelper<T> {
         SpatiumSysHelper {
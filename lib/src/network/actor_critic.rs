@@ -0,0 +1,253 @@
+use super::neural_net::*;
+use super::*;
+use rng::RcRng;
+
+use ndarray::prelude::*;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActorCriticParameters {
+    pub discount_factor: f32,
+    pub learning_rate: f32,
+    pub n_steps: usize,
+    pub entropy_coefficient: f32,
+    pub value_coefficient: f32,
+    pub shared: bool,
+}
+
+impl Default for ActorCriticParameters {
+    fn default() -> Self {
+        ActorCriticParameters {
+            discount_factor: 0.99,
+            learning_rate: 0.01,
+            n_steps: 5,
+            entropy_coefficient: 0.01,
+            value_coefficient: 0.5,
+            shared: true,
+        }
+    }
+}
+
+impl ActorCriticParameters {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.n_steps == 0 {
+            return Err("The actor-critic must learn from at least one step".into());
+        }
+        Ok(())
+    }
+}
+
+struct Transition {
+    state: GameState,
+    action: Action,
+    reward: f32,
+}
+
+/// Advantage actor-critic. Learns online from the last `n_steps`
+/// transitions, bootstrapping the return from the critic's estimate of the
+/// latest state, so no experience buffer is kept.
+pub struct ActorCritic {
+    parameters: ActorCriticParameters,
+    inputs: usize,
+    weights: PolicyWeights,
+    transitions: Vec<Transition>,
+}
+
+impl ActorCritic {
+    pub fn new(parameters: ActorCriticParameters, ios: (usize, usize), rng: RcRng) -> Self {
+        let (inputs, outputs) = ios;
+        let weights = PolicyWeights::new(
+            inputs,
+            outputs,
+            32,
+            parameters.shared,
+            parameters.learning_rate,
+            rng,
+        );
+
+        ActorCritic {
+            parameters: parameters,
+            inputs: inputs,
+            weights: weights,
+            transitions: vec![],
+        }
+    }
+
+    fn run_policy(&self, s: &GameState) -> (Array1<f32>, f32) {
+        let net: PolicyNet = (&self.weights).into();
+        let (probs, value) = net.run(s.into());
+        (probs.row(0).to_owned(), value[0])
+    }
+
+    // the n-step return from each transition, bootstrapped from the
+    // critic unless `next_state` is terminal
+    fn returns(&self, next_state: &GameState, terminal: bool) -> Vec<f32> {
+        let bootstrap = if terminal {
+            0.
+        } else {
+            self.run_policy(next_state).1
+        };
+        let rewards: Vec<f32> = self.transitions.iter().map(|t| t.reward).collect();
+        discounted_returns(&rewards, self.parameters.discount_factor, bootstrap)
+    }
+
+    fn learn(&mut self, next_state: &GameState, terminal: bool) -> f32 {
+        let len = self.transitions.len();
+        let returns = self.returns(next_state, terminal);
+
+        let mut net: PolicyNet = (&self.weights).into();
+
+        let mut states: Array2<f32> = Array::zeros((len, self.inputs));
+        let mut actions = vec![0; len];
+
+        for (i, t) in self.transitions.iter().enumerate() {
+            actions[i] = (&t.action).into();
+
+            let s: Array2<f32> = (&t.state).into();
+            for n in 0..s.shape()[1] {
+                states[[i, n]] = s[[0, n]];
+            }
+        }
+
+        let (_, values) = net.run(states.clone());
+        let advantages: Vec<_> = returns.iter().zip(values.iter()).map(|(g, v)| g - v).collect();
+
+        let loss = net.update(
+            states,
+            &actions,
+            &advantages,
+            &returns,
+            self.parameters.value_coefficient,
+            self.parameters.entropy_coefficient,
+        );
+        self.weights = net.build_weights();
+        loss
+    }
+}
+
+impl Network for ActorCritic {
+    fn test(&self, _sys: &SpatiumSys, game_state: &GameState) -> (Action, f32) {
+        let (probs, value) = self.run_policy(game_state);
+        (greedy_action(&probs).into(), value)
+    }
+
+    fn next_action(
+        &mut self,
+        _sys: &SpatiumSys,
+        rng: Option<RcRng>,
+        s: &GameState,
+    ) -> (Action, f32) {
+        let (probs, value) = self.run_policy(s);
+        let action_i = match rng {
+            Some(mut rng) => sample_action(&probs, &mut rng),
+            None => greedy_action(&probs),
+        };
+        (action_i.into(), value)
+    }
+
+    fn result(
+        &mut self,
+        _sys: &SpatiumSys,
+        _rng: RcRng,
+        s: GameState,
+        a: &Action,
+        s1: &GameState,
+        r: f32,
+        done: bool,
+        truncated: bool,
+    ) -> Metrics {
+        let mut metrics: Metrics = Default::default();
+
+        self.transitions.push(Transition {
+            state: s,
+            action: *a,
+            reward: r,
+        });
+
+        if done || self.transitions.len() >= self.parameters.n_steps {
+            // running out of steps is not a terminal state, so the critic
+            // still values what would have come next
+            let terminal = done && !truncated;
+            let loss = self.learn(s1, terminal);
+            metrics.values.push(("loss".into(), loss));
+            self.transitions.clear();
+        }
+
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{SeedableRng, XorShiftRng};
+    use HeadlessSys;
+
+    fn model(shared: bool) -> ActorCritic {
+        let rng = RcRng::new(Box::new(XorShiftRng::from_seed([1, 2, 3, 4])));
+        let p = ActorCriticParameters {
+            discount_factor: 0.5,
+            learning_rate: 0.1,
+            shared: shared,
+            ..Default::default()
+        };
+        ActorCritic::new(p, (2, 2), rng)
+    }
+
+    #[test]
+    fn test_n_step_returns() {
+        let mut model = model(true);
+        let s = GameState::from_features(vec![1., 0.]);
+        let s1 = GameState::from_features(vec![0., 1.]);
+        for &r in &[1., 0., 2.] {
+            model.transitions.push(Transition {
+                state: s.clone(),
+                action: Action(0),
+                reward: r,
+            });
+        }
+
+        assert_eq!(model.returns(&s1, true), vec![1.5, 1., 2.]);
+
+        let v = model.run_policy(&s1).1;
+        let expected = vec![1.5 + 0.125 * v, 1. + 0.25 * v, 2. + 0.5 * v];
+        let returns = model.returns(&s1, false);
+        for (g, e) in returns.iter().zip(expected.iter()) {
+            assert!((g - e).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_separate_critic() {
+        let mut model = model(false);
+        assert!(model.weights.critic.is_some());
+        let s = GameState::from_features(vec![1., 0.]);
+        let rng = RcRng::new(Box::new(XorShiftRng::from_seed([1, 2, 3, 4])));
+
+        // the critic moves towards the reward of one step episodes
+        let before = model.run_policy(&s).1;
+        for _ in 0..5 {
+            model.result(
+                &HeadlessSys,
+                rng.clone(),
+                s.clone(),
+                &Action(0),
+                &s,
+                1.,
+                true,
+                false,
+            );
+        }
+        let after = model.run_policy(&s).1;
+        assert!((after - 1.).abs() < (before - 1.).abs());
+    }
+
+    #[test]
+    fn test_no_steps() {
+        let p = ActorCriticParameters {
+            n_steps: 0,
+            ..Default::default()
+        };
+        assert!(p.validate().is_err());
+    }
+}
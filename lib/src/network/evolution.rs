@@ -0,0 +1,299 @@
+use super::neural_net::*;
+use super::*;
+use rng::RcRng;
+use env::Env;
+use GenerationResult;
+
+use std::cmp::Ordering;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvolutionStrategiesParameters {
+    pub population_size: usize,
+    pub noise_std: f32,
+    pub learning_rate: f32,
+}
+
+impl Default for EvolutionStrategiesParameters {
+    fn default() -> Self {
+        EvolutionStrategiesParameters {
+            population_size: 20,
+            noise_std: 0.1,
+            learning_rate: 0.05,
+        }
+    }
+}
+
+impl EvolutionStrategiesParameters {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.population_size == 0 {
+            return Err("The population needs at least one mirrored pair".into());
+        }
+        if !(self.noise_std > 0.) {
+            return Err("The noise deviation must be positive".into());
+        }
+        Ok(())
+    }
+}
+
+/// Evolution strategies over the weights of a Q network, following
+/// "Evolution Strategies as a Scalable Alternative to Reinforcement
+/// Learning". Each generation perturbs the weights with mirrored gaussian
+/// noise, plays one episode per candidate on a fresh game and moves the
+/// weights towards the noise weighted by rank normalized fitness.
+/// `population_size` counts the mirrored pairs, so every generation plays
+/// twice that many episodes.
+///
+/// As a model it plays greedily with the current weights and runs one
+/// generation at the end of every episode.
+pub struct EvolutionStrategies {
+    parameters: EvolutionStrategiesParameters,
+    game: GameParameters,
+    weights: Weights,
+    generation: usize,
+    // reported through `Network::generation_result`
+    last_generation: Option<GenerationResult>,
+}
+
+impl EvolutionStrategies {
+    pub fn new(
+        parameters: EvolutionStrategiesParameters,
+        game: GameParameters,
+        ios: (usize, usize),
+        rng: RcRng,
+    ) -> Self {
+        let (inputs, outputs) = ios;
+        let weights = Weights::new(inputs, outputs, 32, 0., rng);
+
+        EvolutionStrategies {
+            parameters: parameters,
+            game: game,
+            weights: weights,
+            generation: 0,
+            last_generation: None,
+        }
+    }
+
+    pub fn weights(&self) -> &Weights {
+        &self.weights
+    }
+
+    /// Evaluate one population and update the weights.
+    pub fn evolve(&mut self, rng: &mut RcRng) -> GenerationResult {
+        let sigma = self.parameters.noise_std;
+
+        let mut noise = vec![];
+        let mut candidates = vec![];
+        let mut seeds = vec![];
+        for _ in 0..self.parameters.population_size {
+            let epsilon = self.weights.noise(rng);
+            // both halves of a mirrored pair play the same board
+            let seed = rng.seed();
+            candidates.push(self.weights.add_scaled(&epsilon, sigma));
+            candidates.push(self.weights.add_scaled(&epsilon, -sigma));
+            seeds.push(seed);
+            seeds.push(seed);
+            noise.push(epsilon);
+        }
+
+        let fitness = evaluate_all(&candidates, &self.game, &seeds);
+        self.update(&noise, &fitness);
+        self.generation += 1;
+
+        generation_result(self.generation, &fitness)
+    }
+
+    // move the weights towards the better half of each mirrored pair, where
+    // `fitness` holds the positive then negative candidate of every pair
+    fn update(&mut self, noise: &[Weights], fitness: &[f32]) {
+        let ranks = centered_ranks(fitness);
+
+        // the gradient estimate averages over every candidate, two per pair
+        let n = fitness.len() as f32;
+        let step = self.parameters.learning_rate / (n * self.parameters.noise_std);
+        for (i, epsilon) in noise.iter().enumerate() {
+            let weight = ranks[2 * i] - ranks[2 * i + 1];
+            self.weights = self.weights.add_scaled(epsilon, step * weight);
+        }
+    }
+}
+
+/// Play a single greedy episode and return its score.
+pub fn play_episode(weights: &Weights, game: &GameParameters, seed: [u32; 4]) -> f32 {
+    let mut env = Env::new(game.clone(), seed).expect("invalid game parameters");
+    let net: NeuralNet = weights.into();
+
+    let mut state = env.reset(seed);
+    loop {
+        let (a_val, _, _) = net.run((&state).into());
+        let action: Action = (a_val[0] as usize).into();
+        let (state1, _, done, info) = env.step(&action);
+        if done {
+            return info.score;
+        }
+        state = state1;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn evaluate_all(candidates: &[Weights], game: &GameParameters, seeds: &[[u32; 4]]) -> Vec<f32> {
+    use rayon::prelude::*;
+    candidates
+        .par_iter()
+        .zip(seeds.par_iter())
+        .map(|(weights, seed)| play_episode(weights, game, *seed))
+        .collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn evaluate_all(candidates: &[Weights], game: &GameParameters, seeds: &[[u32; 4]]) -> Vec<f32> {
+    candidates
+        .iter()
+        .zip(seeds.iter())
+        .map(|(weights, seed)| play_episode(weights, game, *seed))
+        .collect()
+}
+
+pub fn generation_result(generation: usize, fitness: &[f32]) -> GenerationResult {
+    GenerationResult {
+        generation: generation,
+        best_fitness: fitness.iter().cloned().fold(::std::f32::MIN, f32::max),
+        mean_fitness: fitness.iter().fold(0., |a, n| a + n) / fitness.len() as f32,
+    }
+}
+
+/// Order fitness values from worst to best, with NaN worst of all.
+pub fn compare_fitness(a: f32, b: f32) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => a.partial_cmp(&b).unwrap(),
+    }
+}
+
+// fitness rank scaled to [-0.5, 0.5] so the update ignores outliers
+fn centered_ranks(fitness: &[f32]) -> Vec<f32> {
+    let n = fitness.len();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|a, b| compare_fitness(fitness[*a], fitness[*b]));
+
+    let mut ranks = vec![0.; n];
+    if n < 2 {
+        return ranks;
+    }
+    for (rank, i) in order.iter().enumerate() {
+        ranks[*i] = rank as f32 / (n - 1) as f32 - 0.5;
+    }
+    ranks
+}
+
+impl Network for EvolutionStrategies {
+    fn test(&self, _sys: &SpatiumSys, game_state: &GameState) -> (Action, f32) {
+        let net: NeuralNet = (&self.weights).into();
+        let result = net.run(game_state.into());
+        ((result.0[0] as usize).into(), result.2[0])
+    }
+
+    fn next_action(&mut self, sys: &SpatiumSys, _rng: Option<RcRng>, s: &GameState) -> (Action, f32) {
+        self.test(sys, s)
+    }
+
+    fn result(
+        &mut self,
+        _sys: &SpatiumSys,
+        mut rng: RcRng,
+        _s: GameState,
+        _a: &Action,
+        _s1: &GameState,
+        _r: f32,
+        done: bool,
+        _truncated: bool,
+    ) -> Metrics {
+        let mut metrics: Metrics = Default::default();
+
+        if done {
+            let generation = self.evolve(&mut rng);
+            metrics
+                .values
+                .push(("best fitness".into(), generation.best_fitness));
+            metrics
+                .values
+                .push(("mean fitness".into(), generation.mean_fitness));
+            self.last_generation = Some(generation);
+        }
+
+        metrics
+    }
+
+    fn generation_result(&mut self) -> Option<GenerationResult> {
+        self.last_generation.take()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn distance(a: &Weights, b: &Weights) -> f32 {
+        let d = b.add_scaled(a, -1.);
+        [&d.w, &d.b, &d.hw, &d.hb]
+            .iter()
+            .map(|x| x.iter().map(|n| n * n).sum::<f32>())
+            .sum::<f32>()
+            .sqrt()
+    }
+
+    #[test]
+    fn test_centered_ranks() {
+        let ranks = centered_ranks(&[3., -1., 10.]);
+        assert_eq!(ranks, vec![0., -0.5, 0.5]);
+
+        // a NaN score ranks last instead of panicking
+        let ranks = centered_ranks(&[3., ::std::f32::NAN, 10.]);
+        assert_eq!(ranks, vec![0., -0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_update() {
+        let mut rng = RcRng::from_seed([1, 2, 3, 4]);
+        let p = EvolutionStrategiesParameters {
+            population_size: 1,
+            ..Default::default()
+        };
+        let sigma = p.noise_std;
+        let game = GameParameters::Game1(Default::default());
+        let mut model = EvolutionStrategies::new(p, game, (4, 2), rng.clone());
+
+        let before = model.weights().clone();
+        let epsilon = before.noise(&mut rng);
+        let plus = before.add_scaled(&epsilon, sigma);
+        let minus = before.add_scaled(&epsilon, -sigma);
+
+        // the positive half of the pair scored better
+        model.update(&[epsilon], &[1., 0.]);
+        assert!(distance(model.weights(), &plus) < distance(&before, &plus));
+        assert!(distance(model.weights(), &minus) > distance(&before, &minus));
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let empty = EvolutionStrategiesParameters {
+            population_size: 0,
+            ..Default::default()
+        };
+        assert!(empty.validate().is_err());
+
+        let still = EvolutionStrategiesParameters {
+            noise_std: 0.,
+            ..Default::default()
+        };
+        assert!(still.validate().is_err());
+
+        let nan = EvolutionStrategiesParameters {
+            noise_std: ::std::f32::NAN,
+            ..Default::default()
+        };
+        assert!(nan.validate().is_err());
+    }
+}
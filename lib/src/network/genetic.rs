@@ -0,0 +1,249 @@
+use super::evolution::{compare_fitness, evaluate_all, generation_result};
+use super::neural_net::*;
+use super::*;
+use rng::RcRng;
+use GenerationResult;
+
+use rand::Rng;
+use std::cmp::Ordering;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneticParameters {
+    pub population_size: usize,
+    pub elite_count: usize,
+    pub tournament_size: usize,
+    pub mutation_rate: f32,
+    pub mutation_std: f32,
+}
+
+impl Default for GeneticParameters {
+    fn default() -> Self {
+        GeneticParameters {
+            population_size: 30,
+            elite_count: 2,
+            tournament_size: 3,
+            mutation_rate: 0.1,
+            mutation_std: 0.2,
+        }
+    }
+}
+
+impl GeneticParameters {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.population_size == 0 {
+            return Err("The population needs at least one member".into());
+        }
+        if self.elite_count > self.population_size {
+            return Err("eliteCount can't be more than the population size".into());
+        }
+        if self.tournament_size == 0 {
+            return Err("tournamentSize must be at least 1".into());
+        }
+        Ok(())
+    }
+}
+
+/// Neuroevolution with a genetic algorithm. Every generation plays one
+/// episode per member of the population on the same board, keeps the elites
+/// and fills the rest with mutated crossovers of tournament winners.
+///
+/// As a model it plays greedily with the best member of the last generation
+/// and evolves a new generation at the end of every episode.
+pub struct Genetic {
+    parameters: GeneticParameters,
+    game: GameParameters,
+    population: Vec<Weights>,
+    best: Weights,
+    generation: usize,
+    // reported through `Network::generation_result`
+    last_generation: Option<GenerationResult>,
+}
+
+impl Genetic {
+    pub fn new(
+        parameters: GeneticParameters,
+        game: GameParameters,
+        ios: (usize, usize),
+        rng: RcRng,
+    ) -> Self {
+        let (inputs, outputs) = ios;
+        let population: Vec<_> = (0..parameters.population_size)
+            .map(|_| Weights::new(inputs, outputs, 32, 0., rng.clone()))
+            .collect();
+        let best = population[0].clone();
+
+        Genetic {
+            parameters: parameters,
+            game: game,
+            population: population,
+            best: best,
+            generation: 0,
+            last_generation: None,
+        }
+    }
+
+    /// Evaluate the population and breed the next generation.
+    pub fn evolve(&mut self, rng: &mut RcRng) -> GenerationResult {
+        let n = self.population.len();
+
+        let seeds = vec![rng.seed(); n];
+        let fitness = evaluate_all(&self.population, &self.game, &seeds);
+
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|a, b| compare_fitness(fitness[*b], fitness[*a]));
+        self.best = self.population[order[0]].clone();
+
+        let mut next: Vec<_> = order
+            .iter()
+            .take(self.parameters.elite_count)
+            .map(|i| self.population[*i].clone())
+            .collect();
+
+        while next.len() < n {
+            let a = tournament(&fitness, self.parameters.tournament_size, rng);
+            let b = tournament(&fitness, self.parameters.tournament_size, rng);
+            let child = self.population[a].crossover(&self.population[b], rng).mutate(
+                rng,
+                self.parameters.mutation_rate,
+                self.parameters.mutation_std,
+            );
+            next.push(child);
+        }
+
+        self.population = next;
+        self.generation += 1;
+
+        generation_result(self.generation, &fitness)
+    }
+}
+
+// index of the fittest of `size` randomly chosen members
+fn tournament(fitness: &[f32], size: usize, rng: &mut RcRng) -> usize {
+    let mut best = rng.gen_range(0, fitness.len());
+    for _ in 1..size {
+        let i = rng.gen_range(0, fitness.len());
+        if compare_fitness(fitness[i], fitness[best]) == Ordering::Greater {
+            best = i;
+        }
+    }
+    best
+}
+
+impl Network for Genetic {
+    fn test(&self, _sys: &SpatiumSys, game_state: &GameState) -> (Action, f32) {
+        let net: NeuralNet = (&self.best).into();
+        let result = net.run(game_state.into());
+        ((result.0[0] as usize).into(), result.2[0])
+    }
+
+    fn next_action(&mut self, sys: &SpatiumSys, _rng: Option<RcRng>, s: &GameState) -> (Action, f32) {
+        self.test(sys, s)
+    }
+
+    fn result(
+        &mut self,
+        _sys: &SpatiumSys,
+        mut rng: RcRng,
+        _s: GameState,
+        _a: &Action,
+        _s1: &GameState,
+        _r: f32,
+        done: bool,
+        _truncated: bool,
+    ) -> Metrics {
+        let mut metrics: Metrics = Default::default();
+
+        if done {
+            let generation = self.evolve(&mut rng);
+            metrics
+                .values
+                .push(("best fitness".into(), generation.best_fitness));
+            metrics
+                .values
+                .push(("mean fitness".into(), generation.mean_fitness));
+            self.last_generation = Some(generation);
+        }
+
+        metrics
+    }
+
+    fn generation_result(&mut self) -> Option<GenerationResult> {
+        self.last_generation.take()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{SeedableRng, XorShiftRng};
+
+    fn rng() -> RcRng {
+        RcRng::new(Box::new(XorShiftRng::from_seed([1, 2, 3, 4])))
+    }
+
+    #[test]
+    fn test_tournament() {
+        let mut rng = rng();
+        let nan = ::std::f32::NAN;
+
+        // a big enough tournament always includes the fittest
+        assert_eq!(tournament(&[1., nan, 5., 3.], 50, &mut rng), 2);
+        // and a NaN score never wins
+        assert_eq!(tournament(&[nan, -1.], 50, &mut rng), 1);
+    }
+
+    #[test]
+    fn test_crossover_mutate() {
+        let mut rng = rng();
+        let a = Weights::new(3, 2, 4, 0., rng.clone());
+        let b = a.add_scaled(&a.noise(&mut rng), 1.);
+
+        // every weight comes from one parent or the other
+        let child = a.crossover(&b, &mut rng);
+        let same = |x: &Weights, y: &Weights| {
+            x.w.iter().zip(y.w.iter()).filter(|&(x, y)| x == y).count()
+        };
+        let (from_a, from_b) = (same(&child, &a), same(&child, &b));
+        assert_eq!(from_a + from_b, child.w.len());
+        assert!(from_a > 0 && from_b > 0);
+
+        assert_eq!(child.mutate(&mut rng, 0., 1.).w, child.w);
+        let mutated = child.mutate(&mut rng, 1., 1.);
+        assert_eq!(same(&mutated, &child), 0);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let empty = GeneticParameters {
+            population_size: 0,
+            ..Default::default()
+        };
+        assert!(empty.validate().is_err());
+
+        let elites = GeneticParameters {
+            population_size: 2,
+            elite_count: 3,
+            ..Default::default()
+        };
+        assert!(elites.validate().is_err());
+
+        let tournament = GeneticParameters {
+            tournament_size: 0,
+            ..Default::default()
+        };
+        assert!(tournament.validate().is_err());
+
+        // reached through the JSON model parameters too
+        let json = r#"{
+            "type": "Genetic",
+            "populationSize": 0,
+            "eliteCount": 0,
+            "tournamentSize": 3,
+            "mutationRate": 0.1,
+            "mutationStd": 0.2
+        }"#;
+        let err = json.into_parameters().unwrap_err();
+        assert!(err.contains("population"));
+    }
+}
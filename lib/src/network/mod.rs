@@ -2,23 +2,37 @@ use serde;
 use serde_json as json;
 
 use action::*;
-use game::GameState;
+use game::{GameParameters, GameState, Observation};
+use GenerationResult;
 use Metrics;
 use SpatiumSys;
 
 use rng::RcRng;
 
+mod actor_critic;
+mod evolution;
+mod genetic;
 mod neural_net;
+mod oracle;
 mod qtable;
+mod reinforce;
 pub mod single_layer;
 
+pub use self::actor_critic::ActorCriticParameters;
+pub use self::evolution::{EvolutionStrategies, EvolutionStrategiesParameters};
+pub use self::genetic::GeneticParameters;
+pub use self::oracle::OracleParameters;
+pub use self::reinforce::ReinforceParameters;
 pub use self::single_layer::{DynamicValue, SingleLayerNetworkParameters};
 
 pub trait Network {
     fn test(&self, &SpatiumSys, &GameState) -> (Action, f32) {
-        (Action::Down, 0.)
+        (Action(0), 0.)
     }
     fn next_action(&mut self, &SpatiumSys, Option<RcRng>, &GameState) -> (Action, f32);
+    /// Learn from a step: the state, the action taken, the next state, the
+    /// reward, whether the episode is over and whether it was cut short by
+    /// the step limit rather than reaching a terminal state.
     fn result(
         &mut self,
         &SpatiumSys,
@@ -26,9 +40,15 @@ pub trait Network {
         GameState,
         &Action,
         &GameState,
-        usize,
+        f32,
+        bool,
         bool,
     ) -> Metrics;
+    /// The generation the latest `result` finished, for models that evolve
+    /// a population. Each one is only returned once.
+    fn generation_result(&mut self) -> Option<GenerationResult> {
+        None
+    }
 }
 
 pub fn model_descriptions() -> Models {
@@ -43,6 +63,31 @@ pub fn model_descriptions() -> Models {
             name: "Q-Network".into(),
             default_parameters: Default::default(),
         },
+        oracle: ModelDescription {
+            id: "Oracle".into(),
+            name: "Oracle".into(),
+            default_parameters: Default::default(),
+        },
+        reinforce: ModelDescription {
+            id: "Reinforce".into(),
+            name: "REINFORCE".into(),
+            default_parameters: Default::default(),
+        },
+        actor_critic: ModelDescription {
+            id: "ActorCritic".into(),
+            name: "Advantage Actor-Critic".into(),
+            default_parameters: Default::default(),
+        },
+        evolution_strategies: ModelDescription {
+            id: "EvolutionStrategies".into(),
+            name: "Evolution Strategies".into(),
+            default_parameters: Default::default(),
+        },
+        genetic: ModelDescription {
+            id: "Genetic".into(),
+            name: "Genetic Algorithm".into(),
+            default_parameters: Default::default(),
+        },
     }
 }
 
@@ -51,6 +96,11 @@ pub fn model_descriptions() -> Models {
 pub struct Models {
     pub q_table: ModelDescription<()>,
     pub q_network: ModelDescription<single_layer::SingleLayerNetworkParameters>,
+    pub oracle: ModelDescription<oracle::OracleParameters>,
+    pub reinforce: ModelDescription<reinforce::ReinforceParameters>,
+    pub actor_critic: ModelDescription<actor_critic::ActorCriticParameters>,
+    pub evolution_strategies: ModelDescription<evolution::EvolutionStrategiesParameters>,
+    pub genetic: ModelDescription<genetic::GeneticParameters>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -61,11 +111,16 @@ pub struct ModelDescription<P: serde::Serialize> {
     pub default_parameters: P,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ModelParameters {
     QTable,
     QNetwork(single_layer::SingleLayerNetworkParameters),
+    Oracle(oracle::OracleParameters),
+    Reinforce(reinforce::ReinforceParameters),
+    ActorCritic(actor_critic::ActorCriticParameters),
+    EvolutionStrategies(evolution::EvolutionStrategiesParameters),
+    Genetic(genetic::GeneticParameters),
 }
 
 pub trait IntoModelParameters {
@@ -74,23 +129,61 @@ pub trait IntoModelParameters {
 
 impl IntoModelParameters for ModelParameters {
     fn into_parameters(self) -> Result<ModelParameters, String> {
+        self.validate()?;
         Ok(self)
     }
 }
 
 impl<'a> IntoModelParameters for &'a str {
     fn into_parameters(self) -> Result<ModelParameters, String> {
-        json::from_str(self).map_err(|e| format!("{}. String was:\n{}", e, self))
+        let params: ModelParameters =
+            json::from_str(self).map_err(|e| format!("{}. String was:\n{}", e, self))?;
+        params.into_parameters()
     }
 }
 
 impl ModelParameters {
-    pub fn to_model(self, rng: RcRng, ios: (usize, usize)) -> Box<Network + Send> {
-        match self {
-            ModelParameters::QTable => Box::new(qtable::QTable::new()),
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            ModelParameters::ActorCritic(ref p) => p.validate(),
+            ModelParameters::EvolutionStrategies(ref p) => p.validate(),
+            ModelParameters::Genetic(ref p) => p.validate(),
+            _ => Ok(()),
+        }
+    }
+    pub fn to_model(
+        self,
+        rng: RcRng,
+        ios: (usize, usize),
+        game: &GameParameters,
+    ) -> Result<Box<Network + Send>, String> {
+        self.validate()?;
+        let model: Box<Network + Send> = match self {
+            ModelParameters::QTable => Box::new(qtable::QTable::new(ios.1)),
             ModelParameters::QNetwork(p) => {
                 Box::new(single_layer::SingleLayerNetwork::new(p, ios, rng))
             }
-        }
+            ModelParameters::Oracle(p) => match *game {
+                GameParameters::Game1(ref g) if g.observation != Observation::Full => {
+                    return Err("The Oracle model needs to see the full board".into())
+                }
+                GameParameters::Game1(ref g) => {
+                    g.validate()?;
+                    Box::new(oracle::Oracle::new(p, g.clone()))
+                }
+                _ => return Err("The Oracle model only supports Game1".into()),
+            },
+            ModelParameters::Reinforce(p) => Box::new(reinforce::Reinforce::new(p, ios, rng)),
+            ModelParameters::ActorCritic(p) => {
+                Box::new(actor_critic::ActorCritic::new(p, ios, rng))
+            }
+            ModelParameters::EvolutionStrategies(p) => Box::new(
+                evolution::EvolutionStrategies::new(p, game.clone(), ios, rng),
+            ),
+            ModelParameters::Genetic(p) => {
+                Box::new(genetic::Genetic::new(p, game.clone(), ios, rng))
+            }
+        };
+        Ok(model)
     }
 }
@@ -1,5 +1,8 @@
 use ag::{self, Tensor};
 use ndarray::prelude::*;
+use ndarray_rand::RandomExt;
+use rand::Rng;
+use rand::distributions::Normal;
 use RcRng;
 use ag::ops::gradient_descent_ops::*;
 
@@ -36,6 +39,83 @@ impl Weights {
             hb,
         }
     }
+    /// Weights of the same shape drawn from a standard normal distribution.
+    pub fn noise(&self, rng: &mut RcRng) -> Self {
+        fn normal(a: &ArrayD<f32>, rng: &mut RcRng) -> ArrayD<f32> {
+            let n: ArrayD<f64> = Array::random_using(IxDyn(a.shape()), Normal::new(0., 1.), rng);
+            n.mapv(|n| n as f32)
+        }
+
+        Weights {
+            lr: self.lr,
+            inputs: self.inputs,
+            outputs: self.outputs,
+            w: normal(&self.w, rng),
+            b: normal(&self.b, rng),
+            hw: normal(&self.hw, rng),
+            hb: normal(&self.hb, rng),
+        }
+    }
+    /// Returns `self + other * scale`.
+    pub fn add_scaled(&self, other: &Weights, scale: f32) -> Self {
+        Weights {
+            lr: self.lr,
+            inputs: self.inputs,
+            outputs: self.outputs,
+            w: &self.w + &(&other.w * scale),
+            b: &self.b + &(&other.b * scale),
+            hw: &self.hw + &(&other.hw * scale),
+            hb: &self.hb + &(&other.hb * scale),
+        }
+    }
+    /// Uniform crossover, taking each weight from either parent.
+    pub fn crossover(&self, other: &Weights, rng: &mut RcRng) -> Self {
+        fn cross(a: &ArrayD<f32>, b: &ArrayD<f32>, rng: &mut RcRng) -> ArrayD<f32> {
+            let v = a.iter()
+                .zip(b.iter())
+                .map(|(a, b)| if rng.gen() { *a } else { *b })
+                .collect();
+            Array::from_shape_vec(IxDyn(a.shape()), v).expect("crossover shape")
+        }
+
+        Weights {
+            lr: self.lr,
+            inputs: self.inputs,
+            outputs: self.outputs,
+            w: cross(&self.w, &other.w, rng),
+            b: cross(&self.b, &other.b, rng),
+            hw: cross(&self.hw, &other.hw, rng),
+            hb: cross(&self.hb, &other.hb, rng),
+        }
+    }
+    /// Add gaussian noise with standard deviation `std` to each weight with
+    /// probability `rate`.
+    pub fn mutate(&self, rng: &mut RcRng, rate: f32, std: f32) -> Self {
+        fn mutate(
+            a: &ArrayD<f32>,
+            noise: &ArrayD<f32>,
+            rng: &mut RcRng,
+            rate: f32,
+            std: f32,
+        ) -> ArrayD<f32> {
+            let v = a.iter()
+                .zip(noise.iter())
+                .map(|(a, n)| if rng.next_f32() < rate { a + n * std } else { *a })
+                .collect();
+            Array::from_shape_vec(IxDyn(a.shape()), v).expect("mutate shape")
+        }
+
+        let noise = self.noise(rng);
+        Weights {
+            lr: self.lr,
+            inputs: self.inputs,
+            outputs: self.outputs,
+            w: mutate(&self.w, &noise.w, rng, rate, std),
+            b: mutate(&self.b, &noise.b, rng, rate, std),
+            hw: mutate(&self.hw, &noise.hw, rng, rate, std),
+            hb: mutate(&self.hb, &noise.hb, rng, rate, std),
+        }
+    }
 }
 
 impl<'w> From<&'w Weights> for NeuralNet {
@@ -188,6 +268,298 @@ impl NeuralNet {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct PolicyWeights {
+    pub lr: f32,
+    inputs: isize,
+    outputs: isize,
+    pub w: ArrayD<f32>,
+    pub b: ArrayD<f32>,
+    pub pw: ArrayD<f32>,
+    pub pb: ArrayD<f32>,
+    pub vw: ArrayD<f32>,
+    pub vb: ArrayD<f32>,
+    /// Hidden layer for the value head when it does not share the policy's.
+    pub critic: Option<(ArrayD<f32>, ArrayD<f32>)>,
+}
+
+impl PolicyWeights {
+    pub fn new(
+        inputs: usize,
+        outputs: usize,
+        hidden: usize,
+        shared: bool,
+        lr: f32,
+        rng: RcRng,
+    ) -> Self {
+        let arr_rng = ag::ndarray_ext::ArrRng::new(rng);
+
+        let w = arr_rng.glorot_uniform(&[inputs, hidden]);
+        let b = ag::ndarray_ext::zeros(&[1, hidden]);
+
+        let pw = arr_rng.glorot_uniform(&[hidden, outputs]);
+        let pb = ag::ndarray_ext::zeros(&[1, outputs]);
+
+        let vw = arr_rng.glorot_uniform(&[hidden, 1]);
+        let vb = ag::ndarray_ext::zeros(&[1, 1]);
+
+        let critic = if shared {
+            None
+        } else {
+            Some((
+                arr_rng.glorot_uniform(&[inputs, hidden]),
+                ag::ndarray_ext::zeros(&[1, hidden]),
+            ))
+        };
+
+        PolicyWeights {
+            lr: lr,
+            inputs: inputs as isize,
+            outputs: outputs as isize,
+            w: w,
+            b: b,
+            pw: pw,
+            pb: pb,
+            vw: vw,
+            vb: vb,
+            critic: critic,
+        }
+    }
+}
+
+impl<'w> From<&'w PolicyWeights> for PolicyNet {
+    fn from(weights: &'w PolicyWeights) -> Self {
+        let x = ag::placeholder(&[-1, weights.inputs]);
+        // per action weighting of the log probabilities, the negative
+        // advantage on the action taken and zero elsewhere
+        let pg = ag::placeholder(&[-1, weights.outputs]);
+        let returns = ag::placeholder(&[-1, 1]);
+        let value_coef = ag::placeholder(&[-1, 1]);
+        let entropy_coef = ag::placeholder(&[-1, weights.outputs]);
+
+        let w1 = ag::variable(weights.w.clone());
+        let b1 = ag::variable(weights.b.clone());
+        let pw = ag::variable(weights.pw.clone());
+        let pb = ag::variable(weights.pb.clone());
+        let vw = ag::variable(weights.vw.clone());
+        let vb = ag::variable(weights.vb.clone());
+
+        let mut variables = vec![
+            w1.clone(),
+            b1.clone(),
+            pw.clone(),
+            pb.clone(),
+            vw.clone(),
+            vb.clone(),
+        ];
+
+        let z1 = ag::matmul(&x, &w1) + &b1;
+        let zz1 = ag::relu(&z1);
+
+        let logits = ag::matmul(&zz1, &pw) + &pb;
+        let probs = ag::softmax(&logits, 1);
+        let log_probs = ag::log_softmax(&logits, 1);
+
+        let value_hidden = match weights.critic {
+            Some((ref cw, ref cb)) => {
+                let cw = ag::variable(cw.clone());
+                let cb = ag::variable(cb.clone());
+                let z = ag::matmul(&x, &cw) + &cb;
+                variables.push(cw);
+                variables.push(cb);
+                ag::relu(&z)
+            }
+            None => zz1.clone(),
+        };
+        let value = ag::matmul(&value_hidden, &vw) + &vb;
+
+        let pg_loss = ag::reduce_sum(&ag::mul(&pg, &log_probs), &[0, 1], false);
+
+        // sum(p * log p) is the negative entropy, so minimising it keeps the
+        // policy from collapsing too early
+        let neg_entropy = ag::mul(&probs, &log_probs);
+        let entropy_loss = ag::reduce_sum(&ag::mul(&entropy_coef, &neg_entropy), &[0, 1], false);
+
+        let value_se = ag::square(&ag::sub(&returns, &value));
+        let value_loss = ag::reduce_sum(&ag::mul(&value_coef, &value_se), &[0, 1], false);
+
+        let loss = pg_loss + &entropy_loss + &value_loss;
+
+        let update_ops = {
+            let params: Vec<&Tensor> = variables.iter().collect();
+            let grads = ag::grad(&[&loss], &params[..]);
+
+            let mut sgd = sgd::SGD { lr: weights.lr };
+            let mut update_ops = sgd.compute_updates(&params[..], &grads);
+
+            update_ops.insert(0, loss.clone());
+            update_ops
+        };
+
+        PolicyNet {
+            lr: weights.lr,
+            inputs: weights.inputs as usize,
+            outputs: weights.outputs as usize,
+            x: x,
+            pg: pg,
+            returns: returns,
+            value_coef: value_coef,
+            entropy_coef: entropy_coef,
+            variables: variables,
+            update_ops: update_ops,
+            probs: probs,
+            value: value,
+        }
+    }
+}
+
+/// Two layer network with a softmax policy head and a linear value head.
+/// The value head shares the policy's hidden layer unless the weights carry
+/// a separate critic layer.
+pub struct PolicyNet {
+    lr: f32,
+    inputs: usize,
+    outputs: usize,
+    x: Tensor,
+    pg: Tensor,
+    returns: Tensor,
+    value_coef: Tensor,
+    entropy_coef: Tensor,
+    variables: Vec<Tensor>,
+    update_ops: Vec<Tensor>,
+    probs: Tensor,
+    value: Tensor,
+}
+
+impl PolicyNet {
+    // returns probs [len,outputs], value [len]
+    pub fn run(&self, x_val: Array2<f32>) -> (Array2<f32>, Array1<f32>) {
+        let len = x_val.shape()[0];
+
+        let x_val = x_val.into_dyn();
+        let result = ag::eval(&[&self.probs, &self.value], &[(&self.x, &x_val)]);
+
+        let probs = result[0].clone().expect("eval probs");
+        let value = result[1].clone().expect("eval value");
+
+        (
+            probs.into_shape((len, self.outputs)).expect("probs shape"),
+            value.into_shape(len).expect("value shape"),
+        )
+    }
+    /// One gradient step over a batch of transitions. The policy is pushed
+    /// towards actions with positive advantage and the value head towards
+    /// the returns, each term scaled by its coefficient and averaged over
+    /// the batch.
+    pub fn update(
+        &mut self,
+        x_val: Array2<f32>,
+        actions: &[usize],
+        advantages: &[f32],
+        returns: &[f32],
+        value_coef: f32,
+        entropy_coef: f32,
+    ) -> f32 {
+        let len = x_val.shape()[0];
+        let n = len as f32;
+
+        let mut pg_val: Array2<f32> = Array::zeros((len, self.outputs));
+        for i in 0..len {
+            pg_val[[i, actions[i]]] = -advantages[i] / n;
+        }
+        let returns_val = Array::from_shape_vec((len, 1), returns.to_vec()).expect("returns shape");
+        let value_coef_val: Array2<f32> = Array::from_elem((len, 1), value_coef / n);
+        let entropy_coef_val: Array2<f32> = Array::from_elem((len, self.outputs), entropy_coef / n);
+
+        let x_val = x_val.into_dyn();
+        let pg_val = pg_val.into_dyn();
+        let returns_val = returns_val.into_dyn();
+        let value_coef_val = value_coef_val.into_dyn();
+        let entropy_coef_val = entropy_coef_val.into_dyn();
+
+        let feeds = &[
+            (&self.x, &x_val),
+            (&self.pg, &pg_val),
+            (&self.returns, &returns_val),
+            (&self.value_coef, &value_coef_val),
+            (&self.entropy_coef, &entropy_coef_val),
+        ];
+        let result = ag::eval(&self.update_ops, feeds);
+
+        let loss = result[0].as_ref().unwrap();
+        assert_eq!(loss.shape(), [] as [usize; 0]);
+        loss[[]]
+    }
+    pub fn build_weights(&self) -> PolicyWeights {
+        let variables: Vec<&Tensor> = self.variables.iter().collect();
+        let mut new_vars: Vec<ArrayD<f32>> = ag::eval(&variables[..], &[])
+            .into_iter()
+            .map(|v| v.expect("run_update"))
+            .collect();
+
+        let critic = if new_vars.len() > 6 {
+            let cb = new_vars.pop().unwrap();
+            let cw = new_vars.pop().unwrap();
+            Some((cw, cb))
+        } else {
+            None
+        };
+
+        let mut new_vars = new_vars.into_iter();
+        PolicyWeights {
+            lr: self.lr,
+            inputs: self.inputs as isize,
+            outputs: self.outputs as isize,
+            w: new_vars.next().unwrap(),
+            b: new_vars.next().unwrap(),
+            pw: new_vars.next().unwrap(),
+            pb: new_vars.next().unwrap(),
+            vw: new_vars.next().unwrap(),
+            vb: new_vars.next().unwrap(),
+            critic,
+        }
+    }
+}
+
+/// Draw an action index from a probability distribution.
+pub fn sample_action(probs: &Array1<f32>, rng: &mut RcRng) -> usize {
+    let mut n = rng.next_f32();
+    for (i, p) in probs.iter().enumerate() {
+        if n < *p {
+            return i;
+        }
+        n -= *p;
+    }
+    probs.len() - 1
+}
+
+/// The discounted sum of the rewards from each step to the end, carrying on
+/// from `bootstrap` after the last one.
+pub fn discounted_returns(rewards: &[f32], discount_factor: f32, bootstrap: f32) -> Vec<f32> {
+    let mut returns = vec![0.; rewards.len()];
+    let mut g = bootstrap;
+    for i in (0..rewards.len()).rev() {
+        g = rewards[i] + discount_factor * g;
+        returns[i] = g;
+    }
+    returns
+}
+
+/// The most probable action index.
+pub fn greedy_action(probs: &Array1<f32>) -> usize {
+    probs
+        .iter()
+        .enumerate()
+        .fold((0, ::std::f32::MIN), |(max_i, max_p), (i, p)| {
+            if *p > max_p {
+                (i, *p)
+            } else {
+                (max_i, max_p)
+            }
+        })
+        .0
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -0,0 +1,70 @@
+use super::*;
+
+use game::Game1Parameters;
+use action::Direction;
+use game::planner::{self, Layout};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OracleParameters {
+    pub discount_factor: f32,
+}
+
+impl Default for OracleParameters {
+    fn default() -> Self {
+        OracleParameters {
+            discount_factor: 0.9,
+        }
+    }
+}
+
+/// Plays Game1 perfectly by solving each board it is shown. Useful as an
+/// upper bound when judging learned models.
+pub struct Oracle {
+    game: Game1Parameters,
+    discount_factor: f32,
+}
+
+impl Oracle {
+    pub fn new(parameters: OracleParameters, game: Game1Parameters) -> Self {
+        Oracle {
+            game: game,
+            discount_factor: parameters.discount_factor,
+        }
+    }
+    fn best_action(&self, sys: &SpatiumSys, s: &GameState) -> (Action, f32) {
+        let layout = match Layout::from_state(&self.game, s) {
+            Ok(layout) => layout,
+            Err(e) => {
+                sys.fatal(&e);
+                return (Direction::Down.into(), 0.);
+            }
+        };
+        let plan = planner::solve(&self.game, &layout, self.discount_factor);
+        let action = plan.action(&layout.agent)
+            .unwrap_or(Direction::Down.into());
+        (action, plan.value(&layout.agent))
+    }
+}
+
+impl Network for Oracle {
+    fn test(&self, sys: &SpatiumSys, s: &GameState) -> (Action, f32) {
+        self.best_action(sys, s)
+    }
+    fn next_action(&mut self, sys: &SpatiumSys, _rng: Option<RcRng>, s: &GameState) -> (Action, f32) {
+        self.best_action(sys, s)
+    }
+    fn result(
+        &mut self,
+        _sys: &SpatiumSys,
+        _rng: RcRng,
+        _s: GameState,
+        _a: &Action,
+        _s1: &GameState,
+        _r: f32,
+        _done: bool,
+        _truncated: bool,
+    ) -> Metrics {
+        Default::default()
+    }
+}
@@ -8,36 +8,47 @@ use ndarray_rand::RandomExt;
 use rand::distributions::Range;
 use rng::RcRng;
 
+// floats aren't hashable, so features are keyed by their bits
+type Key = (ArrayD<u8>, Vec<u32>);
+
+fn key(s: &GameState) -> Key {
+    (s.arr.clone(), s.features.iter().map(|f| f.to_bits()).collect())
+}
+
 pub struct QTable {
-    q: HashMap<ArrayD<u8>, Array<f32, Ix1>>,
+    actions: usize,
+    q: HashMap<Key, Array<f32, Ix1>>,
 }
 
 impl QTable {
-    pub fn new() -> Self {
-        QTable { q: HashMap::new() }
+    pub fn new(actions: usize) -> Self {
+        QTable {
+            actions: actions,
+            q: HashMap::new(),
+        }
     }
 }
 
 impl Network for QTable {
     fn test(&self, _sys: &SpatiumSys, game_state: &GameState) -> (Action, f32) {
         let q_val = self.q
-            .get(&game_state.arr)
+            .get(&key(game_state))
             .map(|a| a.to_owned())
-            .unwrap_or(Array::zeros(4));
+            .unwrap_or(Array::zeros(self.actions));
         let (action_i, maxq) = argmax(&q_val);
         (action_i.into(), maxq)
     }
 
     fn next_action(&mut self, _: &SpatiumSys, rng: Option<RcRng>, s: &GameState) -> (Action, f32) {
         let q_val = self.q
-            .get(&s.arr)
+            .get(&key(s))
             .map(|a| a.to_owned())
-            .unwrap_or(Array::zeros(4));
+            .unwrap_or(Array::zeros(self.actions));
 
         let mut final_q_val = q_val;
 
         if let Some(mut rng) = rng {
-            let noise: Array1<f32> = Array1::random_using(4, Range::new(0., 3.), &mut rng);
+            let noise: Array1<f32> = Array1::random_using(self.actions, Range::new(0., 3.), &mut rng);
             // self.sys.info(format!("{}", noise));
             final_q_val = final_q_val + noise;
         }
@@ -52,19 +63,20 @@ impl Network for QTable {
         s: GameState,
         a: &Action,
         s1: &GameState,
-        r: usize,
+        r: f32,
         _done: bool,
+        _truncated: bool,
     ) -> Metrics {
         let mut q_val = self.q
-            .get(&s.arr)
+            .get(&key(&s))
             .map(|a| a.to_owned())
-            .unwrap_or(Array::zeros(4));
+            .unwrap_or(Array::zeros(self.actions));
 
         // update Q
         let s1_q_val = self.q
-            .get(&s1.arr)
+            .get(&key(s1))
             .map(|a| a.to_owned())
-            .unwrap_or(Array::zeros(4));
+            .unwrap_or(Array::zeros(self.actions));
         let r1 = argmax(&s1_q_val).1;
 
         let lr = 0.8f32;
@@ -72,8 +84,8 @@ impl Network for QTable {
 
         let action_i = a.into();
         let existing = q_val[[action_i]];
-        q_val[[action_i]] = existing + lr * (r as f32 + y * r1 - existing);
-        self.q.insert(s.arr, q_val);
+        q_val[[action_i]] = existing + lr * (r + y * r1 - existing);
+        self.q.insert(key(&s), q_val);
 
         Default::default()
     }
@@ -95,6 +107,8 @@ fn argmax(arr: &Array1<f32>) -> (usize, f32) {
 #[cfg(test)]
 mod test {
     use super::*;
+    use env::Env;
+    use HeadlessSys;
 
     #[test]
     fn test_argmax() {
@@ -113,4 +127,47 @@ mod test {
         assert_eq!(argmax(&n), (3, -2f32));
     }
 
+    // after training, the greedy policy walks to the goal without stepping
+    // off the cliff
+    #[test]
+    fn test_cliff_walk() {
+        let game = GameParameters::CliffWalk(Default::default());
+        let mut env = Env::new(game, [1, 2, 3, 4]).unwrap();
+        let mut rng = RcRng::from_seed([5, 6, 7, 8]);
+        let mut table = QTable::new(env.action_space().len());
+
+        for _ in 0..200 {
+            let mut state = env.reset(rng.seed());
+            loop {
+                let (action, _) = table.next_action(&HeadlessSys, Some(rng.clone()), &state);
+                let (state1, reward, done, _) = env.step(&action);
+                table.result(
+                    &HeadlessSys,
+                    rng.clone(),
+                    state,
+                    &action,
+                    &state1,
+                    reward,
+                    done,
+                    false,
+                );
+                state = state1;
+                if done {
+                    break;
+                }
+            }
+        }
+
+        let mut state = env.reset(rng.seed());
+        loop {
+            let (action, _) = table.test(&HeadlessSys, &state);
+            let (state1, reward, done, info) = env.step(&action);
+            assert_eq!(reward, -1.);
+            if done {
+                assert!(!info.truncated);
+                break;
+            }
+            state = state1;
+        }
+    }
 }
@@ -0,0 +1,193 @@
+use super::neural_net::*;
+use super::*;
+use rng::RcRng;
+
+use ndarray::prelude::*;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReinforceParameters {
+    pub discount_factor: f32,
+    pub learning_rate: f32,
+    pub entropy_bonus: f32,
+    pub baseline: bool,
+}
+
+impl Default for ReinforceParameters {
+    fn default() -> Self {
+        ReinforceParameters {
+            discount_factor: 0.99,
+            learning_rate: 0.01,
+            entropy_bonus: 0.01,
+            baseline: true,
+        }
+    }
+}
+
+struct Transition {
+    state: GameState,
+    action: Action,
+    reward: f32,
+}
+
+/// Monte Carlo policy gradient. Plays out a whole episode with the current
+/// policy and then updates it once using the discounted returns.
+pub struct Reinforce {
+    parameters: ReinforceParameters,
+    inputs: usize,
+    weights: PolicyWeights,
+    episode: Vec<Transition>,
+}
+
+impl Reinforce {
+    pub fn new(parameters: ReinforceParameters, ios: (usize, usize), rng: RcRng) -> Self {
+        let (inputs, outputs) = ios;
+        let weights = PolicyWeights::new(inputs, outputs, 32, true, parameters.learning_rate, rng);
+
+        Reinforce {
+            parameters: parameters,
+            inputs: inputs,
+            weights: weights,
+            episode: vec![],
+        }
+    }
+
+    fn run_policy(&self, s: &GameState) -> (Array1<f32>, f32) {
+        let net: PolicyNet = (&self.weights).into();
+        let (probs, value) = net.run(s.into());
+        (probs.row(0).to_owned(), value[0])
+    }
+
+    fn learn(&mut self) -> f32 {
+        let len = self.episode.len();
+        let discount_factor = self.parameters.discount_factor;
+
+        let rewards: Vec<f32> = self.episode.iter().map(|t| t.reward).collect();
+        let returns = discounted_returns(&rewards, discount_factor, 0.);
+
+        let mut states: Array2<f32> = Array::zeros((len, self.inputs));
+        let mut actions = vec![0; len];
+
+        for (i, t) in self.episode.iter().enumerate() {
+            actions[i] = (&t.action).into();
+
+            let s: Array2<f32> = (&t.state).into();
+            for n in 0..s.shape()[1] {
+                states[[i, n]] = s[[0, n]];
+            }
+        }
+
+        let mut net: PolicyNet = (&self.weights).into();
+
+        // subtract the learned state value to reduce variance
+        let (advantages, value_coef) = if self.parameters.baseline {
+            let (_, values) = net.run(states.clone());
+            let advantages: Vec<_> = returns.iter().zip(values.iter()).map(|(g, v)| g - v).collect();
+            (advantages, 1.)
+        } else {
+            (returns.clone(), 0.)
+        };
+
+        let loss = net.update(
+            states,
+            &actions,
+            &advantages,
+            &returns,
+            value_coef,
+            self.parameters.entropy_bonus,
+        );
+        self.weights = net.build_weights();
+        loss
+    }
+}
+
+impl Network for Reinforce {
+    fn test(&self, _sys: &SpatiumSys, game_state: &GameState) -> (Action, f32) {
+        let (probs, value) = self.run_policy(game_state);
+        (greedy_action(&probs).into(), value)
+    }
+
+    fn next_action(
+        &mut self,
+        _sys: &SpatiumSys,
+        rng: Option<RcRng>,
+        s: &GameState,
+    ) -> (Action, f32) {
+        let (probs, value) = self.run_policy(s);
+        let action_i = match rng {
+            Some(mut rng) => sample_action(&probs, &mut rng),
+            None => greedy_action(&probs),
+        };
+        (action_i.into(), value)
+    }
+
+    fn result(
+        &mut self,
+        _sys: &SpatiumSys,
+        _rng: RcRng,
+        s: GameState,
+        a: &Action,
+        _s1: &GameState,
+        r: f32,
+        done: bool,
+        _truncated: bool,
+    ) -> Metrics {
+        let mut metrics: Metrics = Default::default();
+
+        self.episode.push(Transition {
+            state: s,
+            action: *a,
+            reward: r,
+        });
+
+        if done {
+            let loss = self.learn();
+            metrics.values.push(("loss".into(), loss));
+            self.episode.clear();
+        }
+
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{SeedableRng, XorShiftRng};
+    use HeadlessSys;
+
+    #[test]
+    fn test_discounted_returns() {
+        let returns = discounted_returns(&[1., 0., 2.], 0.5, 0.);
+        assert_eq!(returns, vec![1.5, 1., 2.]);
+    }
+
+    #[test]
+    fn test_rewarded_action() {
+        let rng = RcRng::new(Box::new(XorShiftRng::from_seed([1, 2, 3, 4])));
+        let p = ReinforceParameters {
+            learning_rate: 0.1,
+            entropy_bonus: 0.,
+            baseline: false,
+            ..Default::default()
+        };
+        let mut model = Reinforce::new(p, (2, 2), rng.clone());
+        let s = GameState::from_features(vec![1., 0.]);
+
+        // one step episodes where the second action pays off
+        let before = model.run_policy(&s).0[1];
+        for _ in 0..5 {
+            model.result(
+                &HeadlessSys,
+                rng.clone(),
+                s.clone(),
+                &Action(1),
+                &s,
+                1.,
+                true,
+                false,
+            );
+        }
+        assert!(model.run_policy(&s).0[1] > before);
+    }
+}
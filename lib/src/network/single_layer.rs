@@ -6,6 +6,9 @@ use ndarray::prelude::*;
 use rand::distributions::IndependentSample;
 use rand::{self, Rng};
 
+// rewards are scaled down so food is worth about 1
+const REWARD_SCALE: f32 = 10.;
+
 #[derive(Clone)]
 struct Experience {
     state: GameState,
@@ -28,7 +31,7 @@ pub struct SingleLayerNetwork {
     experience_buf: Vec<Experience>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DynamicValue {
     pub initial_rate: f32,
@@ -36,7 +39,7 @@ pub struct DynamicValue {
     pub final_episode: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SingleLayerNetworkParameters {
     pub minibatch_size: usize,
@@ -95,7 +98,7 @@ impl SingleLayerNetwork {
         net.run(x_val)
     }
 
-    // returns a_val [len], q_val [len,4], max_q [len]
+    // returns a_val [len], q_val [len,outputs], max_q [len]
     fn run_q_network(
         &self,
         sys: &SpatiumSys,
@@ -104,7 +107,7 @@ impl SingleLayerNetwork {
         self.run_network(sys, x_val, &self.weights)
     }
 
-    // returns a_val [len], q_val [len,4], max_q [len]
+    // returns a_val [len], q_val [len,outputs], max_q [len]
     fn run_target_network(
         &self,
         sys: &SpatiumSys,
@@ -157,7 +160,7 @@ impl SingleLayerNetwork {
 impl Network for SingleLayerNetwork {
     fn test(&self, sys: &SpatiumSys, game_state: &GameState) -> (Action, f32) {
         let result = self.run_q_network(sys, game_state.into());
-        ((result.0[0] as usize).into(), result.2[0] * 10.)
+        ((result.0[0] as usize).into(), result.2[0] * REWARD_SCALE)
     }
 
     fn next_action(
@@ -171,7 +174,7 @@ impl Network for SingleLayerNetwork {
         // exploring
         if let Some(mut rng) = rng {
             if rng.next_f32() < self.explore_chance {
-                let dist = rand::distributions::Range::new(0, 3);
+                let dist = rand::distributions::Range::new(0, self.outputs);
                 self.last_action.0 = dist.ind_sample(&mut rng) as f32;
                 performed = true;
             }
@@ -185,7 +188,7 @@ impl Network for SingleLayerNetwork {
 
         (
             (self.last_action.0 as usize).into(),
-            self.last_action.2 * 10.,
+            self.last_action.2 * REWARD_SCALE,
         )
     }
     fn result(
@@ -195,8 +198,9 @@ impl Network for SingleLayerNetwork {
         s: GameState,
         a: &Action,
         s1: &GameState,
-        r: usize,
+        r: f32,
         done: bool,
+        _truncated: bool,
     ) -> Metrics {
         let mut metrics: Metrics = Default::default();
 
@@ -212,7 +216,7 @@ impl Network for SingleLayerNetwork {
         self.experience_buf.push(Experience {
             state: s.clone(),
             action: *a,
-            reward: r as f32,
+            reward: r,
             next_state: s1.to_owned(),
             done: done,
         });
@@ -227,7 +231,7 @@ impl Network for SingleLayerNetwork {
             for i in 0..minibatch_size {
                 let ex = rng.choose(&self.experience_buf).unwrap().to_owned();
 
-                let reward = if ex.reward > 0. { 1. } else { 0. };
+                let reward = ex.reward / REWARD_SCALE;
                 let s1: Array2<f32> = (&ex.state).into();
                 let s2: Array2<f32> = (&ex.next_state).into();
 
@@ -249,14 +253,14 @@ impl Network for SingleLayerNetwork {
                 let r2 = result.2[[1]];
 
                 let mut target_q = q1_val;
-                assert_eq!(target_q.shape(), &[1, 4]);
+                assert_eq!(target_q.shape(), &[1, self.outputs]);
 
                 let action_i: usize = (&ex.action).into();
 
                 // println!("target: {:?}", result);
                 // println!("ex.action: {:?}", ex.action);
                 // println!("target_q_0: {:?}", target_q);
-                let change_q = (discount_factor * r2) - target_q[[0, action_i]];
+                let change_q = reward + (discount_factor * r2) - target_q[[0, action_i]];
                 target_q[[0, action_i]] = target_q[[0, action_i]] + (0.5 * change_q);
                 if ex.done {
                     target_q[[0, action_i]] = reward;
@@ -313,7 +317,7 @@ mod test {
 
         let mut state: ArrayD<u8> = Array::zeros(IxDyn(&[3, 3]));
         state[[1, 1]] = 1;
-        let state = GameState { arr: state };
+        let state = GameState::grid(state);
 
         // let input = state.map(|x| *x as f32).into_shape(IxDyn(&[1, 9])).unwrap();
         // let pred = net.make_prediction(input);
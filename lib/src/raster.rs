@@ -0,0 +1,534 @@
+use std::collections::HashMap;
+
+use game::RenderingInfo;
+
+/// An RGBA image, row by row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize, color: [u8; 4]) -> Self {
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for _ in 0..width * height {
+            pixels.extend_from_slice(&color);
+        }
+        Frame {
+            width: width,
+            height: height,
+            pixels: pixels,
+        }
+    }
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * self.width + x) * 4;
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: [u8; 4]) {
+        for row in y..(y + height).min(self.height) {
+            for col in x..(x + width).min(self.width) {
+                let i = (row * self.width + col) * 4;
+                self.pixels[i..i + 4].copy_from_slice(&color);
+            }
+        }
+    }
+}
+
+/// Draws a `RenderingInfo` as a square of `cell_size` pixels per cell.
+/// Layers are drawn in order, so later layers cover earlier ones. Layers
+/// without a color in the palette are drawn grey.
+pub struct Rasterizer {
+    palette: HashMap<String, [u8; 4]>,
+    background: [u8; 4],
+    cell_size: usize,
+}
+
+const UNKNOWN_LAYER: [u8; 4] = [128, 128, 128, 255];
+
+impl Default for Rasterizer {
+    fn default() -> Self {
+        let palette = [
+            ("agent", [46, 139, 87, 255]),
+            ("food", [255, 193, 37, 255]),
+            ("block", [64, 64, 64, 255]),
+            ("hazard", [205, 38, 38, 255]),
+            ("rival", [138, 43, 226, 255]),
+            ("wall", [64, 64, 64, 255]),
+            ("exit", [0, 154, 205, 255]),
+            ("cliff", [139, 26, 26, 255]),
+            ("key", [218, 165, 32, 255]),
+            ("door", [139, 69, 19, 255]),
+            ("head", [46, 139, 87, 255]),
+            ("body", [124, 205, 124, 255]),
+            ("track", [64, 64, 64, 255]),
+            ("cart", [0, 0, 205, 255]),
+            ("pole", [205, 133, 63, 255]),
+        ];
+        Rasterizer {
+            palette: palette
+                .iter()
+                .map(|&(name, color)| (name.to_string(), color))
+                .collect(),
+            background: [255, 255, 255, 255],
+            cell_size: 16,
+        }
+    }
+}
+
+impl Rasterizer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn with_color(mut self, layer: &str, color: [u8; 4]) -> Self {
+        self.palette.insert(layer.into(), color);
+        self
+    }
+    pub fn with_background(mut self, color: [u8; 4]) -> Self {
+        self.background = color;
+        self
+    }
+    pub fn with_cell_size(mut self, cell_size: usize) -> Self {
+        self.cell_size = cell_size.max(1);
+        self
+    }
+    pub fn frame(&self, info: &RenderingInfo) -> Frame {
+        let size = self.cell_size;
+        let mut frame = Frame::new(info.width * size, info.height * size, self.background);
+        for layer in info.layers.iter() {
+            let color = match self.palette.get(&layer.name) {
+                Some(color) => *color,
+                None => UNKNOWN_LAYER,
+            };
+            for p in layer.points.iter() {
+                // points off the board are clipped by fill
+                frame.fill(p.x * size, p.y * size, size, size, color);
+            }
+        }
+        frame
+    }
+    pub fn frames(&self, infos: &[RenderingInfo]) -> Vec<Frame> {
+        infos.iter().map(|info| self.frame(info)).collect()
+    }
+}
+
+fn check_sizes(frames: &[Frame]) -> Result<(usize, usize), String> {
+    let (width, height) = match frames.first() {
+        Some(frame) => (frame.width, frame.height),
+        None => return Err("There are no frames to encode".into()),
+    };
+    if width == 0 || height == 0 || width > 0xffff || height > 0xffff {
+        return Err(format!("Can't encode a {}x{} frame", width, height));
+    }
+    if frames
+        .iter()
+        .any(|frame| frame.width != width || frame.height != height)
+    {
+        return Err("All the frames must be the same size".into());
+    }
+    Ok((width, height))
+}
+
+/// Encode the frames as a looping animated GIF, showing each frame for
+/// `delay` hundredths of a second. Alpha is dropped, and the frames can use
+/// at most 256 colors between them.
+pub fn encode_gif(frames: &[Frame], delay: u16) -> Result<Vec<u8>, String> {
+    let (width, height) = check_sizes(frames)?;
+
+    let mut colors: Vec<[u8; 3]> = vec![];
+    let mut indices: HashMap<[u8; 3], u8> = HashMap::new();
+    for frame in frames {
+        for pixel in frame.pixels.chunks(4) {
+            let rgb = [pixel[0], pixel[1], pixel[2]];
+            if !indices.contains_key(&rgb) {
+                if colors.len() == 256 {
+                    return Err("A GIF can't have more than 256 colors".into());
+                }
+                indices.insert(rgb, colors.len() as u8);
+                colors.push(rgb);
+            }
+        }
+    }
+    // the color table has 2^bits entries
+    let mut bits = 1;
+    while (1 << bits) < colors.len() {
+        bits += 1;
+    }
+
+    let mut out = vec![];
+    out.extend_from_slice(b"GIF89a");
+    push_u16_le(&mut out, width as u16);
+    push_u16_le(&mut out, height as u16);
+    out.push(0xf0 | (bits - 1) as u8);
+    out.push(0);
+    out.push(0);
+    for i in 0..1 << bits {
+        match colors.get(i) {
+            Some(rgb) => out.extend_from_slice(rgb),
+            None => out.extend_from_slice(&[0, 0, 0]),
+        }
+    }
+    // loop forever
+    out.extend_from_slice(&[0x21, 0xff, 0x0b]);
+    out.extend_from_slice(b"NETSCAPE2.0");
+    out.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+    let min_code_size = bits.max(2);
+    for frame in frames {
+        out.extend_from_slice(&[0x21, 0xf9, 0x04, 0x00]);
+        push_u16_le(&mut out, delay);
+        out.extend_from_slice(&[0x00, 0x00]);
+
+        out.push(0x2c);
+        push_u16_le(&mut out, 0);
+        push_u16_le(&mut out, 0);
+        push_u16_le(&mut out, width as u16);
+        push_u16_le(&mut out, height as u16);
+        out.push(0);
+
+        let pixels: Vec<u8> = frame
+            .pixels
+            .chunks(4)
+            .map(|p| indices[&[p[0], p[1], p[2]]])
+            .collect();
+        out.push(min_code_size as u8);
+        for block in lzw(&pixels, min_code_size).chunks(255) {
+            out.push(block.len() as u8);
+            out.extend_from_slice(block);
+        }
+        out.push(0);
+    }
+    out.push(0x3b);
+    Ok(out)
+}
+
+// Writes codes least significant bit first, as GIF expects.
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    n: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.acc |= (code as u32) << self.n;
+        self.n += size;
+        while self.n >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.n -= 8;
+        }
+    }
+    fn finish(mut self) -> Vec<u8> {
+        if self.n > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+const MAX_CODE: u16 = 4096;
+
+fn lzw(pixels: &[u8], min_code_size: usize) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut writer = BitWriter {
+        bytes: vec![],
+        acc: 0,
+        n: 0,
+    };
+    let mut codes: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut size = min_code_size as u32 + 1;
+    writer.write(clear, size);
+
+    let mut current: Option<u16> = None;
+    for &pixel in pixels {
+        let prefix = match current {
+            None => {
+                current = Some(pixel as u16);
+                continue;
+            }
+            Some(prefix) => prefix,
+        };
+        if let Some(&code) = codes.get(&(prefix, pixel)) {
+            current = Some(code);
+            continue;
+        }
+
+        writer.write(prefix, size);
+        if next < MAX_CODE {
+            codes.insert((prefix, pixel), next);
+            next += 1;
+            // the decoder adds its copy of the code one step later
+            if next > 1 << size && size < 12 {
+                size += 1;
+            }
+        } else {
+            writer.write(clear, size);
+            codes.clear();
+            next = end + 1;
+            size = min_code_size as u32 + 1;
+        }
+        current = Some(pixel as u16);
+    }
+    if let Some(code) = current {
+        writer.write(code, size);
+        if next == 1 << size && size < 12 {
+            size += 1;
+        }
+    }
+    writer.write(end, size);
+    writer.finish()
+}
+
+/// Encode the frames side by side as a single RGBA PNG.
+pub fn encode_png_strip(frames: &[Frame]) -> Result<Vec<u8>, String> {
+    let (width, height) = check_sizes(frames)?;
+    let strip_width = width * frames.len();
+    if strip_width > 0x7fff_ffff {
+        return Err("The strip is too wide for a PNG".into());
+    }
+
+    // each row starts with filter type 0, none
+    let mut raw = Vec::with_capacity((strip_width * 4 + 1) * height);
+    for y in 0..height {
+        raw.push(0);
+        for frame in frames {
+            let row = y * width * 4;
+            raw.extend_from_slice(&frame.pixels[row..row + width * 4]);
+        }
+    }
+
+    let mut out = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+    let mut header = vec![];
+    push_u32_be(&mut header, strip_width as u32);
+    push_u32_be(&mut header, height as u32);
+    // 8 bits per channel, RGBA, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+    push_chunk(&mut out, b"IHDR", &header);
+    push_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+    push_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
+
+fn push_u16_le(out: &mut Vec<u8>, n: u16) {
+    out.push(n as u8);
+    out.push((n >> 8) as u8);
+}
+
+fn push_u32_be(out: &mut Vec<u8>, n: u32) {
+    out.extend_from_slice(&[(n >> 24) as u8, (n >> 16) as u8, (n >> 8) as u8, n as u8]);
+}
+
+fn push_chunk(out: &mut Vec<u8>, kind: &[u8], data: &[u8]) {
+    push_u32_be(out, data.len() as u32);
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    push_u32_be(out, crc);
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// A zlib stream of uncompressed deflate blocks. Frames are mostly flat
+// color, so this is larger than it could be, but it keeps the library free
+// of a compression dependency.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = data.chunks(0xffff).collect();
+    if blocks.is_empty() {
+        out.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+    }
+    for (i, block) in blocks.iter().enumerate() {
+        out.push(if i + 1 == blocks.len() { 1 } else { 0 });
+        let len = block.len() as u16;
+        push_u16_le(&mut out, len);
+        push_u16_le(&mut out, !len);
+        out.extend_from_slice(block);
+    }
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    push_u32_be(&mut out, (b << 16) | a);
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use game::{Point, RenderingLayer};
+
+    fn info(agent: (usize, usize)) -> RenderingInfo {
+        RenderingInfo {
+            width: 3,
+            height: 2,
+            layers: vec![
+                RenderingLayer {
+                    name: "agent".into(),
+                    points: vec![Point {
+                        x: agent.0,
+                        y: agent.1,
+                    }],
+                },
+                RenderingLayer {
+                    name: "portal".into(),
+                    points: vec![Point { x: 2, y: 1 }, Point { x: 5, y: 5 }],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_frame() {
+        let rasterizer = Rasterizer::new()
+            .with_cell_size(2)
+            .with_color("agent", [1, 2, 3, 255]);
+        let frame = rasterizer.frame(&info((1, 0)));
+        assert_eq!((frame.width, frame.height), (6, 4));
+        assert_eq!(frame.pixel(0, 0), [255, 255, 255, 255]);
+        assert_eq!(frame.pixel(2, 0), [1, 2, 3, 255]);
+        assert_eq!(frame.pixel(3, 1), [1, 2, 3, 255]);
+        assert_eq!(frame.pixel(5, 3), UNKNOWN_LAYER);
+    }
+
+    #[test]
+    fn test_encode_gif() {
+        let rasterizer = Rasterizer::new().with_cell_size(5);
+        let frames = rasterizer.frames(&[info((0, 0)), info((1, 0)), info((1, 1))]);
+        let gif = encode_gif(&frames, 10).unwrap();
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(gif[gif.len() - 1], 0x3b);
+        // one graphic control block per frame
+        let controls = gif.windows(3).filter(|w| w == &[0x21, 0xf9, 0x04]).count();
+        assert_eq!(controls, 3);
+
+        assert!(encode_gif(&[], 10).is_err());
+    }
+
+    // a plain GIF decoder, returning the pixels and how many clear codes
+    // it read
+    fn unlzw(data: &[u8], min_code_size: usize) -> (Vec<u8>, usize) {
+        let clear = 1usize << min_code_size;
+        let end = clear + 1;
+        let reset = || -> Vec<Vec<u8>> { (0..end + 1).map(|i| vec![i as u8]).collect() };
+
+        let mut table = reset();
+        let mut size = min_code_size + 1;
+        let mut prev: Option<usize> = None;
+        let mut pixels = vec![];
+        let mut clears = 0;
+        let mut bit = 0;
+        loop {
+            let mut code = 0;
+            for i in 0..size {
+                let b = data[(bit + i) / 8] >> ((bit + i) % 8) & 1;
+                code |= (b as usize) << i;
+            }
+            bit += size;
+
+            if code == clear {
+                table = reset();
+                size = min_code_size + 1;
+                prev = None;
+                clears += 1;
+                continue;
+            }
+            if code == end {
+                return (pixels, clears);
+            }
+            let entry = if code < table.len() {
+                table[code].clone()
+            } else {
+                // the code the encoder has only just added
+                assert_eq!(code, table.len());
+                let mut entry = table[prev.unwrap()].clone();
+                let first = entry[0];
+                entry.push(first);
+                entry
+            };
+            pixels.extend_from_slice(&entry);
+            if let Some(p) = prev {
+                if table.len() < MAX_CODE as usize {
+                    let mut added = table[p].clone();
+                    added.push(entry[0]);
+                    table.push(added);
+                }
+            }
+            if table.len() == 1 << size && size < 12 {
+                size += 1;
+            }
+            prev = Some(code);
+        }
+    }
+
+    #[test]
+    fn test_lzw() {
+        let frame = Rasterizer::new().frame(&info((1, 1)));
+        let small: Vec<u8> = frame.pixels.chunks(4).map(|p| p[0] % 4).collect();
+        let mut cases = vec![vec![], vec![3], vec![0; 1000], small];
+
+        // varied enough to fill the code table, forcing a clear code
+        let mut x: u32 = 1;
+        let noisy = (0..50_000)
+            .map(|_| {
+                x = x.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (x >> 16) as u8 % 4
+            })
+            .collect();
+        cases.push(noisy);
+
+        for (i, pixels) in cases.iter().enumerate() {
+            let (decoded, clears) = unlzw(&lzw(pixels, 2), 2);
+            assert_eq!(&decoded, pixels);
+            if i == cases.len() - 1 {
+                assert!(clears > 1);
+            }
+        }
+
+        // a wider palette starts with longer codes
+        let pixels: Vec<u8> = (0..5000).map(|i| (i * 7 % 200) as u8).collect();
+        assert_eq!(unlzw(&lzw(&pixels, 8), 8).0, pixels);
+    }
+
+    #[test]
+    fn test_encode_png_strip() {
+        let frames = Rasterizer::new().frames(&[info((0, 0)), info((2, 0))]);
+        let png = encode_png_strip(&frames).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+        // the header holds the width of both frames
+        assert_eq!(&png[16..24], &[0, 0, 0, 96, 0, 0, 0, 32]);
+
+        let small = Rasterizer::new().with_cell_size(1).frame(&info((0, 0)));
+        assert!(encode_png_strip(&[frames[0].clone(), small]).is_err());
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+    }
+}
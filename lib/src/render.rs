@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use action::Direction;
+use game::{Point, RenderingInfo};
+
+/// Foreground colors for ANSI terminals.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    fn code(&self) -> u8 {
+        match *self {
+            Color::Black => 30,
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+        }
+    }
+}
+
+/// How one cell is drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Glyph {
+    pub symbol: char,
+    pub color: Option<Color>,
+}
+
+impl Glyph {
+    pub fn new(symbol: char, color: Option<Color>) -> Self {
+        Glyph {
+            symbol: symbol,
+            color: color,
+        }
+    }
+}
+
+/// Draws a `RenderingInfo` as text with one character per cell and one line
+/// per row. Layers are drawn in order, so later layers cover earlier ones.
+/// Layers without a glyph are drawn with the first letter of their name.
+pub struct TextRenderer {
+    glyphs: HashMap<String, Glyph>,
+    empty: Glyph,
+    arrows: Option<Color>,
+    ansi: bool,
+}
+
+impl Default for TextRenderer {
+    fn default() -> Self {
+        let glyphs = [
+            ("agent", '@', Color::Green),
+            ("food", '*', Color::Yellow),
+            ("block", '#', Color::White),
+            ("hazard", 'X', Color::Red),
+            ("rival", 'R', Color::Magenta),
+            ("wall", '#', Color::White),
+            ("exit", 'E', Color::Cyan),
+            ("cliff", '~', Color::Red),
+            ("key", 'k', Color::Yellow),
+            ("door", 'D', Color::Blue),
+            ("head", '@', Color::Green),
+            ("body", 'o', Color::Green),
+            ("track", '=', Color::White),
+            ("cart", 'C', Color::Blue),
+            ("pole", '|', Color::Yellow),
+        ];
+        TextRenderer {
+            glyphs: glyphs
+                .iter()
+                .map(|&(name, symbol, color)| (name.to_string(), Glyph::new(symbol, Some(color))))
+                .collect(),
+            empty: Glyph::new('.', None),
+            arrows: Some(Color::Blue),
+            ansi: false,
+        }
+    }
+}
+
+impl TextRenderer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn with_glyph(mut self, layer: &str, glyph: Glyph) -> Self {
+        self.glyphs.insert(layer.into(), glyph);
+        self
+    }
+    pub fn with_empty(mut self, glyph: Glyph) -> Self {
+        self.empty = glyph;
+        self
+    }
+    /// Color the cells with ANSI escape codes.
+    pub fn with_ansi(mut self, ansi: bool) -> Self {
+        self.ansi = ansi;
+        self
+    }
+    pub fn render(&self, info: &RenderingInfo) -> String {
+        self.render_with_policy(info, &[])
+    }
+    /// Draw an arrow in each given cell for the action the policy picks
+    /// there. Arrows are drawn before the layers, so they only show in
+    /// cells that are otherwise empty.
+    pub fn render_with_policy(&self, info: &RenderingInfo, policy: &[(Point, Direction)]) -> String {
+        let mut cells = vec![self.empty; info.width * info.height];
+        {
+            let mut draw = |p: &Point, glyph: Glyph| {
+                // rendering info from outside the library may be off the board
+                if p.x < info.width && p.y < info.height {
+                    cells[p.y * info.width + p.x] = glyph;
+                }
+            };
+
+            for &(ref p, direction) in policy {
+                draw(p, Glyph::new(arrow(direction), self.arrows));
+            }
+            for layer in info.layers.iter() {
+                let glyph = match self.glyphs.get(&layer.name) {
+                    Some(glyph) => *glyph,
+                    None => Glyph::new(layer.name.chars().next().unwrap_or('?'), None),
+                };
+                for p in layer.points.iter() {
+                    draw(p, glyph);
+                }
+            }
+        }
+
+        let mut s = String::new();
+        for row in cells.chunks(info.width.max(1)) {
+            for glyph in row {
+                match glyph.color {
+                    Some(color) if self.ansi => {
+                        s.push_str(&format!("\x1b[{}m{}\x1b[0m", color.code(), glyph.symbol))
+                    }
+                    _ => s.push(glyph.symbol),
+                }
+            }
+            s.push('\n');
+        }
+        s
+    }
+}
+
+fn arrow(direction: Direction) -> char {
+    match direction {
+        Direction::Up => '^',
+        Direction::Right => '>',
+        Direction::Down => 'v',
+        Direction::Left => '<',
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use game::RenderingLayer;
+
+    fn layer(name: &str, points: &[(usize, usize)]) -> RenderingLayer {
+        RenderingLayer {
+            name: name.into(),
+            points: points.iter().map(|&(x, y)| Point { x: x, y: y }).collect(),
+        }
+    }
+
+    fn info() -> RenderingInfo {
+        RenderingInfo {
+            width: 3,
+            height: 2,
+            layers: vec![
+                layer("food", &[(2, 0)]),
+                layer("agent", &[(0, 1)]),
+                layer("portal", &[(1, 1)]),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let renderer = TextRenderer::new();
+        assert_eq!(renderer.render(&info()), "..*\n@p.\n");
+
+        let renderer = renderer.with_glyph("agent", Glyph::new('A', None));
+        assert_eq!(renderer.render(&info()), "..*\nAp.\n");
+    }
+
+    #[test]
+    fn test_render_ansi() {
+        let renderer = TextRenderer::new().with_ansi(true);
+        let s = renderer.render(&info());
+        assert!(s.starts_with("..\x1b[33m*\x1b[0m\n"));
+        assert!(s.contains("\x1b[32m@\x1b[0m"));
+    }
+
+    #[test]
+    fn test_render_policy() {
+        let policy = vec![
+            (Point { x: 0, y: 0 }, Direction::Right),
+            (Point { x: 1, y: 0 }, Direction::Right),
+            (Point { x: 0, y: 1 }, Direction::Up),
+            (Point { x: 2, y: 1 }, Direction::Up),
+        ];
+        let s = TextRenderer::new().render_with_policy(&info(), &policy);
+        assert_eq!(s, ">>*\n@p^\n");
+    }
+}
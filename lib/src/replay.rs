@@ -0,0 +1,155 @@
+use action::Action;
+use game::{Game, GameParameters, RenderingInfo};
+use rng::RcRng;
+use HeadlessSys;
+
+/// A recorded episode. Games are deterministic given the seeds they are
+/// built and reset with, so the game, the seeds and the actions taken are
+/// enough to play the episode again.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Replay {
+    pub game: GameParameters,
+    pub game_seed: [u32; 4],
+    pub episode_seed: [u32; 4],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<usize>,
+    /// The first frame, to check the rebuilt episode against.
+    pub layout: RenderingInfo,
+    /// Every agent's action for each step in turn.
+    pub actions: Vec<usize>,
+}
+
+impl Replay {
+    pub fn new(
+        game: GameParameters,
+        game_seed: [u32; 4],
+        episode_seed: [u32; 4],
+        level: Option<usize>,
+        layout: RenderingInfo,
+    ) -> Self {
+        Replay {
+            game: game,
+            game_seed: game_seed,
+            episode_seed: episode_seed,
+            level: level,
+            layout: layout,
+            actions: vec![],
+        }
+    }
+    /// Record one step, with an action per agent.
+    pub fn push(&mut self, actions: &[Action]) {
+        self.actions.extend(actions.iter().map(|a| a.0));
+    }
+    /// Play the episode again, returning the frame after the reset followed
+    /// by one frame per step.
+    pub fn frames(&self) -> Result<Vec<RenderingInfo>, String> {
+        self.game.validate()?;
+        let mut game = self.game
+            .clone()
+            .into_game(RcRng::from_seed(self.game_seed));
+        if let Some(level) = self.level {
+            game.set_level(level);
+        }
+        game.reset_agents(RcRng::from_seed(self.episode_seed));
+
+        let layout = game.rendering_info();
+        if layout != self.layout {
+            return Err("The replay doesn't match its recorded layout".into());
+        }
+
+        let (_, actions) = game.io();
+        let agents = game.agents();
+        if self.actions.len() % agents != 0 {
+            return Err(format!(
+                "{} actions can't be shared between {} agents",
+                self.actions.len(),
+                agents
+            ));
+        }
+        if let Some(a) = self.actions.iter().find(|a| **a >= actions.len()) {
+            return Err(format!("Bad action value: {}", a));
+        }
+
+        let steps = self.actions.len() / agents;
+        let mut frames = vec![layout];
+        for (i, step) in self.actions.chunks(agents).enumerate() {
+            let step: Vec<_> = step.iter().map(|a| Action(*a)).collect();
+            let (_, _, done) = game.step_agents(&HeadlessSys, &step);
+            frames.push(game.rendering_info());
+            if done && i + 1 < steps {
+                return Err(format!("The episode ended after {} of {} steps", i + 1, steps));
+            }
+        }
+        Ok(frames)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate rand;
+
+    use super::*;
+    use game::{ArenaParameters, Game1Parameters, HazardPolicy};
+    use network::ModelParameters;
+    use serde_json;
+    use spatium::tests::SpatiumDummy;
+    use Spatium;
+
+    // Play two episodes, checking each replay rebuilds the frames seen.
+    fn episodes(game: GameParameters, agents: usize) -> Vec<Replay> {
+        let rng = RcRng::new(Box::new(rand::weak_rng()));
+        let model = ModelParameters::QTable;
+        let mut spat = Spatium::new(game, model, SpatiumDummy {}, rng.clone(), 100).unwrap();
+
+        let mut episodes = vec![];
+        let mut frames = vec![];
+        while episodes.len() < 2 {
+            let result = spat.step(rng.clone());
+            frames.push(result.rendering_info.unwrap());
+            if let Some(replay) = result.replay {
+                // the replay goes through json like a saved file would
+                let json = serde_json::to_string(&replay).unwrap();
+                let replay: Replay = serde_json::from_str(&json).unwrap();
+                assert_eq!(replay.frames().unwrap(), frames);
+                assert_eq!(replay.actions.len() / agents + 1, frames.len());
+
+                episodes.push(replay);
+                frames = vec![];
+            }
+        }
+        episodes
+    }
+
+    #[test]
+    fn test_frames() {
+        let game = GameParameters::Game1(Game1Parameters {
+            max_steps: 30,
+            random: true,
+            hazards: 2,
+            hazard_policy: HazardPolicy::RandomWalk,
+            slip: 0.2,
+            ..Default::default()
+        });
+        let episodes = episodes(game, 1);
+        assert!(episodes[0].episode_seed != episodes[1].episode_seed);
+
+        let mut broken = episodes[0].clone();
+        broken.episode_seed = episodes[1].episode_seed;
+        assert!(broken.frames().is_err());
+    }
+
+    #[test]
+    fn test_frames_agents() {
+        let game = GameParameters::Arena(ArenaParameters {
+            max_steps: 20,
+            agents: 3,
+            ..Default::default()
+        });
+        let episodes = episodes(game, 3);
+
+        let mut broken = episodes[0].clone();
+        broken.actions.pop();
+        assert!(broken.frames().is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use rand::Rng;
+use rand::{Rng, SeedableRng, XorShiftRng};
 use std::rc::Rc;
 use std::cell::RefCell;
 
@@ -12,6 +12,19 @@ impl RcRng {
             rng: Rc::new(RefCell::new(rng)),
         }
     }
+    /// A `XorShiftRng` behind an `RcRng`, for reproducible runs.
+    pub fn from_seed(seed: [u32; 4]) -> Self {
+        RcRng::new(Box::new(XorShiftRng::from_seed(seed)))
+    }
+    /// A seed for `XorShiftRng`, which must not be all zeros.
+    pub fn seed(&mut self) -> [u32; 4] {
+        [
+            self.next_u32(),
+            self.next_u32(),
+            self.next_u32(),
+            self.next_u32() | 1,
+        ]
+    }
 }
 
 impl Clone for RcRng {
@@ -3,7 +3,11 @@ use super::*;
 struct RunningArgs {
     episode: usize,
     step: usize,
-    game_state: GameState,
+    // one per agent
+    game_states: Vec<GameState>,
+    // sum of the rewards so far, per agent
+    scores: Vec<f32>,
+    replay: Replay,
 }
 
 enum EpisodeState {
@@ -15,7 +19,12 @@ pub struct Spatium<T: SpatiumSys> {
     sys: SpatiumSysHelper<T>,
     max_episodes: usize,
     game: Box<Game + Send>,
-    network: Box<Network + Send>,
+    game_parameters: GameParameters,
+    // replays rebuild the game from this
+    game_seed: [u32; 4],
+    actions: ActionSpace,
+    // one per agent
+    networks: Vec<Box<Network + Send>>,
     episode_state: Option<EpisodeState>,
 }
 
@@ -24,7 +33,7 @@ impl<T: SpatiumSys> Spatium<T> {
         game_parameters: G,
         model_parameters: P,
         sys: T,
-        rng: RcRng,
+        mut rng: RcRng,
         max_episodes: usize,
     ) -> Result<Spatium<T>, String> {
         let game_parameters = game_parameters.into_parameters()?;
@@ -33,33 +42,61 @@ impl<T: SpatiumSys> Spatium<T> {
         let model_parameters = model_parameters.into_parameters()?;
         sys.info(&format!("Parsed model params: {:?}", model_parameters));
 
-        let game = game_parameters.into_game(rng.clone());
-        let network = model_parameters.to_model(rng, game.io());
+        let game_seed = rng.seed();
+        let game = game_parameters
+            .clone()
+            .into_game(RcRng::from_seed(game_seed));
+        let (inputs, actions) = game.io();
+        let mut networks = vec![];
+        for _ in 0..game.agents() {
+            networks.push(model_parameters.clone().to_model(
+                rng.clone(),
+                (inputs, actions.len()),
+                &game_parameters,
+            )?);
+        }
 
         let n = Spatium {
             sys: SpatiumSysHelper::new(sys),
             max_episodes: max_episodes,
             game: game,
-            network: network,
+            game_parameters: game_parameters,
+            game_seed: game_seed,
+            actions: actions,
+            networks: networks,
             episode_state: None,
         };
         n.sys.info("Running Spatium");
         Ok(n)
     }
+    /// Evaluate each agent's network in turn.
     pub fn eval(&self) {
-        self.game.eval(&self.sys, &self.network);
+        for network in self.networks.iter() {
+            self.game.eval(&self.sys, network);
+        }
     }
-    fn process_inital_state(&mut self, rng: RcRng, episode: usize) -> (EpisodeState, StepResult) {
-        let (game_state, _score, _done) = self.game.reset(rng);
+    fn process_inital_state(&mut self, mut rng: RcRng, episode: usize) -> (EpisodeState, StepResult) {
+        let episode_seed = rng.seed();
+        let (game_states, rewards, _done) = self.game.reset_agents(RcRng::from_seed(episode_seed));
 
         let rendering_info = self.game.rendering_info();
+        let level = self.game.level();
+        let replay = Replay::new(
+            self.game_parameters.clone(),
+            self.game_seed,
+            episode_seed,
+            level,
+            rendering_info.clone(),
+        );
         (
             EpisodeState::Running(RunningArgs {
                 episode: episode,
                 step: 1,
-                game_state: game_state,
+                game_states: game_states,
+                scores: rewards,
+                replay: replay,
             }),
-            StepResult::new(episode, 0, "None".into(), false, rendering_info),
+            StepResult::new(episode, 0, "None".into(), false, rendering_info).with_level(level),
         )
     }
     fn process_running_state(
@@ -73,53 +110,96 @@ impl<T: SpatiumSys> Spatium<T> {
         let RunningArgs {
             episode,
             step,
-            game_state,
+            game_states,
+            scores,
+            mut replay,
         } = args;
 
-        // get next action from model
-        let (action, _val) = self.network
-            .next_action(&*sys, Some(rng.clone()), &game_state);
-
-        // advance game using action
-        let (game_state1, score1, done) = self.game.step(&self.sys, &action);
-
-        // pass result to model and collect any metrics
-        let metrics = self.network.result(
-            &*sys,
-            rng.clone(),
-            game_state,
-            &action,
-            &game_state1,
-            score1,
-            done,
-        );
+        // get next action from each agent's model
+        let mut actions = vec![];
+        for (network, game_state) in self.networks.iter_mut().zip(game_states.iter()) {
+            let (action, _val) = network.next_action(&*sys, Some(rng.clone()), game_state);
+            actions.push(action);
+        }
+
+        // advance game using actions
+        let level = self.game.level();
+        let (game_states1, rewards, done) = self.game.step_agents(&self.sys, &actions);
+        let truncated = self.game.truncated();
+        let scores1: Vec<f32> = scores.iter().zip(rewards.iter()).map(|(s, r)| s + r).collect();
+        replay.push(&actions);
+
+        // pass results to models and collect what each agent reports
+        let mut metrics = vec![];
+        let mut generation_results = vec![];
+        for (i, game_state) in game_states.into_iter().enumerate() {
+            metrics.push(self.networks[i].result(
+                &*sys,
+                rng.clone(),
+                game_state,
+                &actions[i],
+                &game_states1[i],
+                rewards[i],
+                done,
+                truncated,
+            ));
+            generation_results.push(self.networks[i].generation_result());
+        }
 
-        let result = StepResult::new(
+        let mut result = StepResult::new(
             episode,
             step,
-            format!("{}", action),
+            self.actions.name(&actions[0]).into(),
             episode > self.max_episodes,
             self.game.rendering_info(),
-        ).with_metrics(metrics);
+        ).with_metrics(metrics[0].clone())
+            .with_generation_result(generation_results[0].clone())
+            .with_level(level);
+        if actions.len() > 1 {
+            let agents = actions
+                .iter()
+                .zip(rewards.iter())
+                .zip(metrics.into_iter().zip(generation_results.into_iter()))
+                .map(|((action, reward), (metrics, generation_result))| AgentStep {
+                    action: self.actions.name(action).into(),
+                    reward: *reward,
+                    metrics: metrics,
+                    generation_result: generation_result,
+                })
+                .collect();
+            result = result.with_agents(agents);
+        }
 
         if done {
             self.sys
                 .debug(&format!("Episode {} complete at step {}", episode, step));
+            // shared rewards would count everything eaten once per agent
+            let score: f32 = if self.game.shared_reward() {
+                scores1[0]
+            } else {
+                scores1.iter().sum()
+            };
+            let scores = if scores1.len() > 1 { Some(scores1) } else { None };
             (
                 EpisodeState::Init {
                     episode: episode + 1,
                 },
-                result.with_episode_result(EpisodeResult {
-                    steps: step,
-                    score: score1 as f32,
-                }),
+                result
+                    .with_episode_result(EpisodeResult {
+                        steps: step,
+                        score: score,
+                        scores: scores,
+                    })
+                    .with_replay(replay),
             )
         } else {
             (
                 EpisodeState::Running(RunningArgs {
                     episode: episode,
                     step: step + 1,
-                    game_state: game_state1,
+                    game_states: game_states1,
+                    scores: scores1,
+                    replay: replay,
                 }),
                 result,
             )
@@ -142,7 +222,7 @@ pub mod tests {
     extern crate rand;
 
     use super::*;
-    use game::Game1Parameters;
+    use game::{ArenaParameters, Game1Parameters};
     use network::SingleLayerNetworkParameters;
     use network::single_layer::DynamicValue;
     use rayon::prelude::*;
@@ -189,6 +269,55 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn it_runs_agents() {
+        let rng = RcRng::new(Box::new(rand::weak_rng()));
+        let game = GameParameters::Arena(ArenaParameters {
+            max_steps: 10,
+            ..Default::default()
+        });
+        let model = ModelParameters::QTable;
+        let mut spat = Spatium::new(game, model, SpatiumDummy {}, rng.clone(), 10).unwrap();
+
+        // skip the reset
+        spat.step(rng.clone());
+        loop {
+            let result = spat.step(rng.clone());
+            let agents = result.agents.unwrap();
+            assert_eq!(agents.len(), 2);
+            assert_eq!(result.action, agents[0].action);
+            if let Some(ep_result) = result.episode_result {
+                let scores = ep_result.scores.unwrap();
+                assert_eq!(scores.len(), 2);
+                assert_eq!(scores[0] + scores[1], ep_result.score);
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn it_shares_agent_scores() {
+        let rng = RcRng::new(Box::new(rand::weak_rng()));
+        let game = GameParameters::Arena(ArenaParameters {
+            max_steps: 10,
+            shared_reward: true,
+            ..Default::default()
+        });
+        let model = ModelParameters::QTable;
+        let mut spat = Spatium::new(game, model, SpatiumDummy {}, rng.clone(), 10).unwrap();
+
+        // the team's score counts every food once
+        loop {
+            let result = spat.step(rng.clone());
+            if let Some(ep_result) = result.episode_result {
+                let scores = ep_result.scores.unwrap();
+                assert_eq!(scores[0], scores[1]);
+                assert_eq!(scores[0], ep_result.score);
+                break;
+            }
+        }
+    }
+
     #[test]
     fn it_parameters() {
         // let model = ModelParameters::QNetwork(Default::default());
@@ -246,6 +375,7 @@ pub mod tests {
                             max_steps: max_steps,
                             size: 10,
                             random: true,
+                            ..Default::default()
                         });
                         // println!("Game parameters: {}", serde_json::to_string(&game).unwrap());
 
@@ -32,6 +32,12 @@ pub extern "C" fn model_descriptions() -> *mut c_char {
     CString::new(s).unwrap().into_raw()
 }
 
+#[no_mangle]
+pub extern "C" fn game_descriptions() -> *mut c_char {
+    let s = json::to_string(&spatium_lib::game_descriptions()).unwrap();
+    CString::new(s).unwrap().into_raw()
+}
+
 #[no_mangle]
 pub extern "C" fn setup(model_params: *mut c_char, max_episodes: usize) -> *mut c_char {
     let model_params = unsafe { CStr::from_ptr(model_params).to_string_lossy().into_owned() };